@@ -0,0 +1,596 @@
+// Color type and conversions shared by all devices
+
+use anyhow::{Context, Result};
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// 24-bit RGB color
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb::new(0, 0, 0);
+    pub const WHITE: Rgb = Rgb::new(255, 255, 255);
+    pub const RED: Rgb = Rgb::new(255, 0, 0);
+    pub const BLUE: Rgb = Rgb::new(0, 0, 255);
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Rgb { r, g, b }
+    }
+
+    /// Scale all components by `percent` (0-100)
+    pub fn scale(self, percent: u8) -> Rgb {
+        let scale = |c: u8| (c as u16 * percent.min(100) as u16 / 100) as u8;
+        Rgb::new(scale(self.r), scale(self.g), scale(self.b))
+    }
+}
+
+// Names accepted in place of an RGB triple
+const NAMED_COLORS: &[(&str, Rgb)] = &[
+    ("black", Rgb::BLACK),
+    ("white", Rgb::WHITE),
+    ("red", Rgb::RED),
+    ("green", Rgb::new(0, 255, 0)),
+    ("blue", Rgb::BLUE),
+    ("yellow", Rgb::new(255, 255, 0)),
+    ("cyan", Rgb::new(0, 255, 255)),
+    ("magenta", Rgb::new(255, 0, 255)),
+    ("orange", Rgb::new(255, 128, 0)),
+    ("purple", Rgb::new(128, 0, 255)),
+];
+
+/// Parse "r,g,b", "#rrggbb"/"rrggbb" or a color name
+impl FromStr for Rgb {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+
+        if let Some(&(_, rgb)) = NAMED_COLORS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(s))
+        {
+            return Ok(rgb);
+        }
+
+        if s.contains(',') {
+            let parts: Vec<&str> = s.split(',').map(str::trim).collect();
+            if parts.len() != 3 {
+                anyhow::bail!("Invalid color {:?} (expected r,g,b)", s);
+            }
+            let component = |p: &str| -> Result<u8> {
+                p.parse()
+                    .with_context(|| format!("Invalid color component {:?} (expected 0-255)", p))
+            };
+            return Ok(Rgb::new(
+                component(parts[0])?,
+                component(parts[1])?,
+                component(parts[2])?,
+            ));
+        }
+
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        if hex.len() == 6 {
+            if let Ok(val) = u32::from_str_radix(hex, 16) {
+                return Ok(Rgb::new((val >> 16) as u8, (val >> 8) as u8, val as u8));
+            }
+        }
+
+        anyhow::bail!(
+            "Invalid color {:?} (expected r,g,b, #rrggbb or a color name)",
+            s
+        )
+    }
+}
+
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{},{},{}", self.r, self.g, self.b)
+    }
+}
+
+/// Linear interpolation between two colors, `t` in 0.0..=1.0
+pub fn lerp_color(from: Rgb, to: Rgb, t: f32) -> Rgb {
+    let t = t.clamp(0.0, 1.0);
+    let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Rgb::new(lerp(from.r, to.r), lerp(from.g, to.g), lerp(from.b, to.b))
+}
+
+/// Color at `t` on a gradient of (position, color) stops sorted by position
+///
+/// Positions before the first or after the last stop take that stop's color.
+pub fn gradient_at(stops: &[(f32, Rgb)], t: f32) -> Rgb {
+    let Some(&(first_pos, first)) = stops.first() else {
+        return Rgb::BLACK;
+    };
+    if t <= first_pos {
+        return first;
+    }
+    for pair in stops.windows(2) {
+        let ((from_pos, from), (to_pos, to)) = (pair[0], pair[1]);
+        if t <= to_pos {
+            return lerp_color(
+                from,
+                to,
+                (t - from_pos) / (to_pos - from_pos).max(f32::EPSILON),
+            );
+        }
+    }
+    stops[stops.len() - 1].1
+}
+
+/// Color at `hue` degrees on the HSV wheel, with saturation and value in 0.0..=1.0
+pub fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> Rgb {
+    let h = hue.rem_euclid(360.0) / 60.0;
+    let s = saturation.clamp(0.0, 1.0);
+    let v = value.clamp(0.0, 1.0);
+    let c = v * s;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = v - c;
+    let to_u8 = |v: f32| ((v + m) * 255.0).round() as u8;
+    Rgb::new(to_u8(r), to_u8(g), to_u8(b))
+}
+
+/// Most common color of an image, by k-means clustering of a thumbnail
+///
+/// The image is shrunk to at most 64x64 first; the centroid of the largest
+/// cluster is returned.
+pub fn dominant_color(img: &DynamicImage) -> Rgb {
+    const K: usize = 5;
+    const ITERATIONS: usize = 10;
+
+    let pixels: Vec<[f32; 3]> = img
+        .thumbnail(64, 64)
+        .to_rgb8()
+        .pixels()
+        .map(|p| [p[0] as f32, p[1] as f32, p[2] as f32])
+        .collect();
+    if pixels.is_empty() {
+        return Rgb::BLACK;
+    }
+
+    // Deterministic start: pixels spread evenly through the image
+    let mut centroids: Vec<[f32; 3]> = (0..K).map(|i| pixels[i * pixels.len() / K]).collect();
+    let mut counts = [0usize; K];
+    for _ in 0..ITERATIONS {
+        let mut sums = [[0f32; 3]; K];
+        counts = [0; K];
+        for p in &pixels {
+            let nearest = (0..K)
+                .min_by(|&a, &b| {
+                    let dist = |c: &[f32; 3]| (0..3).map(|i| (p[i] - c[i]).powi(2)).sum::<f32>();
+                    dist(&centroids[a]).total_cmp(&dist(&centroids[b]))
+                })
+                .unwrap_or(0);
+            counts[nearest] += 1;
+            for i in 0..3 {
+                sums[nearest][i] += p[i];
+            }
+        }
+        for k in 0..K {
+            if counts[k] > 0 {
+                centroids[k] = sums[k].map(|sum| sum / counts[k] as f32);
+            }
+        }
+    }
+
+    let largest = (0..K).max_by_key(|&k| counts[k]).unwrap_or(0);
+    let [r, g, b] = centroids[largest].map(|v| v.round().clamp(0.0, 255.0) as u8);
+    Rgb::new(r, g, b)
+}
+
+/// Dominant color of each of `n` vertical strips of an image, left to right
+///
+/// The image is shrunk to at most 128x128 first. Each strip's pixels are
+/// quantized by median cut, and the average of the densest box (most pixels
+/// per unit of color volume) is returned.
+pub fn extract_zone_colors(img: &DynamicImage, n: usize) -> Vec<Rgb> {
+    const MEDIAN_CUT_BOXES: usize = 8;
+
+    let thumb = img.thumbnail(128, 128).to_rgb8();
+    let (width, height) = (thumb.width() as usize, thumb.height() as usize);
+    (0..n)
+        .map(|zone| {
+            let start = (zone * width / n).min(width.saturating_sub(1));
+            let end = ((zone + 1) * width / n).clamp(start + 1, width.max(1));
+            let mut pixels: Vec<[u8; 3]> = (start..end)
+                .flat_map(|x| (0..height).map(move |y| (x, y)))
+                .map(|(x, y)| thumb.get_pixel(x as u32, y as u32).0)
+                .collect();
+            median_cut_dominant(&mut pixels, MEDIAN_CUT_BOXES)
+        })
+        .collect()
+}
+
+/// Split the pixels into up to `max_boxes` boxes by median cut and return the
+/// average color of the densest one
+fn median_cut_dominant(pixels: &mut [[u8; 3]], max_boxes: usize) -> Rgb {
+    // Per-channel (max - min) of a box
+    fn ranges(pixels: &[[u8; 3]]) -> [u8; 3] {
+        [0, 1, 2].map(|c| {
+            let values = pixels.iter().map(|p| p[c]);
+            values.clone().max().unwrap_or(0) - values.min().unwrap_or(0)
+        })
+    }
+
+    let mut boxes: Vec<&mut [[u8; 3]]> = vec![pixels];
+    while boxes.len() < max_boxes {
+        // Split the box with the widest channel range along that channel
+        let Some((index, channel, _)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.len() >= 2)
+            .flat_map(|(i, b)| {
+                ranges(b)
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(c, r)| (i, c, r))
+            })
+            .filter(|&(_, _, range)| range > 0)
+            .max_by_key(|&(_, _, range)| range)
+        else {
+            break;
+        };
+        let pixels = boxes.swap_remove(index);
+        pixels.sort_unstable_by_key(|p| p[channel]);
+        let (low, high) = pixels.split_at_mut(pixels.len() / 2);
+        boxes.push(low);
+        boxes.push(high);
+    }
+
+    let density = |b: &[[u8; 3]]| {
+        let volume: f32 = ranges(b).iter().map(|&r| r as f32 + 1.0).product();
+        b.len() as f32 / volume
+    };
+    let Some(densest) = boxes
+        .iter()
+        .filter(|b| !b.is_empty())
+        .max_by(|a, b| density(a).total_cmp(&density(b)))
+    else {
+        return Rgb::BLACK;
+    };
+    let mut sums = [0u64; 3];
+    for p in densest.iter() {
+        for c in 0..3 {
+            sums[c] += p[c] as u64;
+        }
+    }
+    let [r, g, b] = sums.map(|sum| (sum / densest.len() as u64) as u8);
+    Rgb::new(r, g, b)
+}
+
+/// Approximate RGB of a black body at `kelvin` (1000-40000 K)
+///
+/// Uses Tanner Helland's curve fit of the CIE 1964 color matching data.
+pub fn kelvin_to_rgb(kelvin: u32) -> Rgb {
+    let temp = kelvin.clamp(1000, 40000) as f64 / 100.0;
+    let clamp = |v: f64| v.clamp(0.0, 255.0).round() as u8;
+
+    let r = if temp <= 66.0 {
+        255.0
+    } else {
+        329.698727446 * (temp - 60.0).powf(-0.1332047592)
+    };
+    let g = if temp <= 66.0 {
+        99.4708025861 * temp.ln() - 161.1195681661
+    } else {
+        288.1221695283 * (temp - 60.0).powf(-0.0755148492)
+    };
+    let b = if temp >= 66.0 {
+        255.0
+    } else if temp <= 19.0 {
+        0.0
+    } else {
+        138.5177312231 * (temp - 10.0).ln() - 305.0447927307
+    };
+
+    Rgb::new(clamp(r), clamp(g), clamp(b))
+}
+
+/// Names accepted by `colormap_lookup`
+pub const COLORMAP_NAMES: &[&str] = &["viridis", "plasma", "inferno", "magma"];
+
+/// Color at `t` (0.0..=1.0) in a matplotlib colormap, or None for an unknown name
+///
+/// Interpolates linearly between the 256 table entries.
+pub fn colormap_lookup(name: &str, t: f32) -> Option<Rgb> {
+    let table = match name.to_ascii_lowercase().as_str() {
+        "viridis" => &VIRIDIS,
+        "plasma" => &PLASMA,
+        "inferno" => &INFERNO,
+        "magma" => &MAGMA,
+        _ => return None,
+    };
+    let entry = |i: usize| Rgb::new(table[i * 3], table[i * 3 + 1], table[i * 3 + 2]);
+    let pos = t.clamp(0.0, 1.0) * 255.0;
+    let i = (pos.floor() as usize).min(254);
+    Some(lerp_color(entry(i), entry(i + 1), pos - i as f32))
+}
+
+// 256-entry colormaps from matplotlib (van der Walt, Smith and Firing, CC0)
+
+#[rustfmt::skip]
+static VIRIDIS: [u8; 256 * 3] = [
+    68, 1, 84, 68, 2, 86, 69, 4, 87, 69, 5, 89,
+    70, 7, 90, 70, 8, 92, 70, 10, 93, 70, 11, 94,
+    71, 13, 96, 71, 14, 97, 71, 16, 99, 71, 17, 100,
+    71, 19, 101, 72, 20, 103, 72, 22, 104, 72, 23, 105,
+    72, 24, 106, 72, 26, 108, 72, 27, 109, 72, 28, 110,
+    72, 29, 111, 72, 31, 112, 72, 32, 113, 72, 33, 115,
+    72, 35, 116, 72, 36, 117, 72, 37, 118, 72, 38, 119,
+    72, 40, 120, 72, 41, 121, 71, 42, 122, 71, 44, 122,
+    71, 45, 123, 71, 46, 124, 71, 47, 125, 70, 48, 126,
+    70, 50, 126, 70, 51, 127, 70, 52, 128, 69, 53, 129,
+    69, 55, 129, 69, 56, 130, 68, 57, 131, 68, 58, 131,
+    68, 59, 132, 67, 61, 132, 67, 62, 133, 66, 63, 133,
+    66, 64, 134, 66, 65, 134, 65, 66, 135, 65, 68, 135,
+    64, 69, 136, 64, 70, 136, 63, 71, 136, 63, 72, 137,
+    62, 73, 137, 62, 74, 137, 62, 76, 138, 61, 77, 138,
+    61, 78, 138, 60, 79, 138, 60, 80, 139, 59, 81, 139,
+    59, 82, 139, 58, 83, 139, 58, 84, 140, 57, 85, 140,
+    57, 86, 140, 56, 88, 140, 56, 89, 140, 55, 90, 140,
+    55, 91, 141, 54, 92, 141, 54, 93, 141, 53, 94, 141,
+    53, 95, 141, 52, 96, 141, 52, 97, 141, 51, 98, 141,
+    51, 99, 141, 50, 100, 142, 50, 101, 142, 49, 102, 142,
+    49, 103, 142, 49, 104, 142, 48, 105, 142, 48, 106, 142,
+    47, 107, 142, 47, 108, 142, 46, 109, 142, 46, 110, 142,
+    46, 111, 142, 45, 112, 142, 45, 113, 142, 44, 113, 142,
+    44, 114, 142, 44, 115, 142, 43, 116, 142, 43, 117, 142,
+    42, 118, 142, 42, 119, 142, 42, 120, 142, 41, 121, 142,
+    41, 122, 142, 41, 123, 142, 40, 124, 142, 40, 125, 142,
+    39, 126, 142, 39, 127, 142, 39, 128, 142, 38, 129, 142,
+    38, 130, 142, 38, 130, 142, 37, 131, 142, 37, 132, 142,
+    37, 133, 142, 36, 134, 142, 36, 135, 142, 35, 136, 142,
+    35, 137, 142, 35, 138, 141, 34, 139, 141, 34, 140, 141,
+    34, 141, 141, 33, 142, 141, 33, 143, 141, 33, 144, 141,
+    33, 145, 140, 32, 146, 140, 32, 146, 140, 32, 147, 140,
+    31, 148, 140, 31, 149, 139, 31, 150, 139, 31, 151, 139,
+    31, 152, 139, 31, 153, 138, 31, 154, 138, 30, 155, 138,
+    30, 156, 137, 30, 157, 137, 31, 158, 137, 31, 159, 136,
+    31, 160, 136, 31, 161, 136, 31, 161, 135, 31, 162, 135,
+    32, 163, 134, 32, 164, 134, 33, 165, 133, 33, 166, 133,
+    34, 167, 133, 34, 168, 132, 35, 169, 131, 36, 170, 131,
+    37, 171, 130, 37, 172, 130, 38, 173, 129, 39, 173, 129,
+    40, 174, 128, 41, 175, 127, 42, 176, 127, 44, 177, 126,
+    45, 178, 125, 46, 179, 124, 47, 180, 124, 49, 181, 123,
+    50, 182, 122, 52, 182, 121, 53, 183, 121, 55, 184, 120,
+    56, 185, 119, 58, 186, 118, 59, 187, 117, 61, 188, 116,
+    63, 188, 115, 64, 189, 114, 66, 190, 113, 68, 191, 112,
+    70, 192, 111, 72, 193, 110, 74, 193, 109, 76, 194, 108,
+    78, 195, 107, 80, 196, 106, 82, 197, 105, 84, 197, 104,
+    86, 198, 103, 88, 199, 101, 90, 200, 100, 92, 200, 99,
+    94, 201, 98, 96, 202, 96, 99, 203, 95, 101, 203, 94,
+    103, 204, 92, 105, 205, 91, 108, 205, 90, 110, 206, 88,
+    112, 207, 87, 115, 208, 86, 117, 208, 84, 119, 209, 83,
+    122, 209, 81, 124, 210, 80, 127, 211, 78, 129, 211, 77,
+    132, 212, 75, 134, 213, 73, 137, 213, 72, 139, 214, 70,
+    142, 214, 69, 144, 215, 67, 147, 215, 65, 149, 216, 64,
+    152, 216, 62, 155, 217, 60, 157, 217, 59, 160, 218, 57,
+    162, 218, 55, 165, 219, 54, 168, 219, 52, 170, 220, 50,
+    173, 220, 48, 176, 221, 47, 178, 221, 45, 181, 222, 43,
+    184, 222, 41, 186, 222, 40, 189, 223, 38, 192, 223, 37,
+    194, 223, 35, 197, 224, 33, 200, 224, 32, 202, 225, 31,
+    205, 225, 29, 208, 225, 28, 210, 226, 27, 213, 226, 26,
+    216, 226, 25, 218, 227, 25, 221, 227, 24, 223, 227, 24,
+    226, 228, 24, 229, 228, 25, 231, 228, 25, 234, 229, 26,
+    236, 229, 27, 239, 229, 28, 241, 229, 29, 244, 230, 30,
+    246, 230, 32, 248, 230, 33, 251, 231, 35, 253, 231, 37,
+];
+
+#[rustfmt::skip]
+static PLASMA: [u8; 256 * 3] = [
+    13, 8, 135, 16, 7, 136, 19, 7, 137, 22, 7, 138,
+    25, 6, 140, 27, 6, 141, 29, 6, 142, 32, 6, 143,
+    34, 6, 144, 36, 6, 145, 38, 5, 145, 40, 5, 146,
+    42, 5, 147, 44, 5, 148, 46, 5, 149, 47, 5, 150,
+    49, 5, 151, 51, 5, 151, 53, 4, 152, 55, 4, 153,
+    56, 4, 154, 58, 4, 154, 60, 4, 155, 62, 4, 156,
+    63, 4, 156, 65, 4, 157, 67, 3, 158, 68, 3, 158,
+    70, 3, 159, 72, 3, 159, 73, 3, 160, 75, 3, 161,
+    76, 2, 161, 78, 2, 162, 80, 2, 162, 81, 2, 163,
+    83, 2, 163, 85, 2, 164, 86, 1, 164, 88, 1, 164,
+    89, 1, 165, 91, 1, 165, 92, 1, 166, 94, 1, 166,
+    96, 1, 166, 97, 0, 167, 99, 0, 167, 100, 0, 167,
+    102, 0, 167, 103, 0, 168, 105, 0, 168, 106, 0, 168,
+    108, 0, 168, 110, 0, 168, 111, 0, 168, 113, 0, 168,
+    114, 1, 168, 116, 1, 168, 117, 1, 168, 119, 1, 168,
+    120, 1, 168, 122, 2, 168, 123, 2, 168, 125, 3, 168,
+    126, 3, 168, 128, 4, 168, 129, 4, 167, 131, 5, 167,
+    132, 5, 167, 134, 6, 166, 135, 7, 166, 136, 8, 166,
+    138, 9, 165, 139, 10, 165, 141, 11, 165, 142, 12, 164,
+    143, 13, 164, 145, 14, 163, 146, 15, 163, 148, 16, 162,
+    149, 17, 161, 150, 19, 161, 152, 20, 160, 153, 21, 159,
+    154, 22, 159, 156, 23, 158, 157, 24, 157, 158, 25, 157,
+    160, 26, 156, 161, 27, 155, 162, 29, 154, 163, 30, 154,
+    165, 31, 153, 166, 32, 152, 167, 33, 151, 168, 34, 150,
+    170, 35, 149, 171, 36, 148, 172, 38, 148, 173, 39, 147,
+    174, 40, 146, 176, 41, 145, 177, 42, 144, 178, 43, 143,
+    179, 44, 142, 180, 46, 141, 181, 47, 140, 182, 48, 139,
+    183, 49, 138, 184, 50, 137, 186, 51, 136, 187, 52, 136,
+    188, 53, 135, 189, 55, 134, 190, 56, 133, 191, 57, 132,
+    192, 58, 131, 193, 59, 130, 194, 60, 129, 195, 61, 128,
+    196, 62, 127, 197, 64, 126, 198, 65, 125, 199, 66, 124,
+    200, 67, 123, 201, 68, 122, 202, 69, 122, 203, 70, 121,
+    204, 71, 120, 204, 73, 119, 205, 74, 118, 206, 75, 117,
+    207, 76, 116, 208, 77, 115, 209, 78, 114, 210, 79, 113,
+    211, 81, 113, 212, 82, 112, 213, 83, 111, 213, 84, 110,
+    214, 85, 109, 215, 86, 108, 216, 87, 107, 217, 88, 106,
+    218, 90, 106, 218, 91, 105, 219, 92, 104, 220, 93, 103,
+    221, 94, 102, 222, 95, 101, 222, 97, 100, 223, 98, 99,
+    224, 99, 99, 225, 100, 98, 226, 101, 97, 226, 102, 96,
+    227, 104, 95, 228, 105, 94, 229, 106, 93, 229, 107, 93,
+    230, 108, 92, 231, 110, 91, 231, 111, 90, 232, 112, 89,
+    233, 113, 88, 233, 114, 87, 234, 116, 87, 235, 117, 86,
+    235, 118, 85, 236, 119, 84, 237, 121, 83, 237, 122, 82,
+    238, 123, 81, 239, 124, 81, 239, 126, 80, 240, 127, 79,
+    240, 128, 78, 241, 129, 77, 241, 131, 76, 242, 132, 75,
+    243, 133, 75, 243, 135, 74, 244, 136, 73, 244, 137, 72,
+    245, 139, 71, 245, 140, 70, 246, 141, 69, 246, 143, 68,
+    247, 144, 68, 247, 145, 67, 247, 147, 66, 248, 148, 65,
+    248, 149, 64, 249, 151, 63, 249, 152, 62, 249, 154, 62,
+    250, 155, 61, 250, 156, 60, 250, 158, 59, 251, 159, 58,
+    251, 161, 57, 251, 162, 56, 252, 163, 56, 252, 165, 55,
+    252, 166, 54, 252, 168, 53, 252, 169, 52, 253, 171, 51,
+    253, 172, 51, 253, 174, 50, 253, 175, 49, 253, 177, 48,
+    253, 178, 47, 253, 180, 47, 253, 181, 46, 254, 183, 45,
+    254, 184, 44, 254, 186, 44, 254, 187, 43, 254, 189, 42,
+    254, 190, 42, 254, 192, 41, 253, 194, 41, 253, 195, 40,
+    253, 197, 39, 253, 198, 39, 253, 200, 39, 253, 202, 38,
+    253, 203, 38, 252, 205, 37, 252, 206, 37, 252, 208, 37,
+    252, 210, 37, 251, 211, 36, 251, 213, 36, 251, 215, 36,
+    250, 216, 36, 250, 218, 36, 249, 220, 36, 249, 221, 37,
+    248, 223, 37, 248, 225, 37, 247, 226, 37, 247, 228, 37,
+    246, 230, 38, 246, 232, 38, 245, 233, 38, 245, 235, 39,
+    244, 237, 39, 243, 238, 39, 243, 240, 39, 242, 242, 39,
+    241, 244, 38, 241, 245, 37, 240, 247, 36, 240, 249, 33,
+];
+
+#[rustfmt::skip]
+static INFERNO: [u8; 256 * 3] = [
+    0, 0, 4, 1, 0, 5, 1, 1, 6, 1, 1, 8,
+    2, 1, 10, 2, 2, 12, 2, 2, 14, 3, 2, 16,
+    4, 3, 18, 4, 3, 20, 5, 4, 23, 6, 4, 25,
+    7, 5, 27, 8, 5, 29, 9, 6, 31, 10, 7, 34,
+    11, 7, 36, 12, 8, 38, 13, 8, 41, 14, 9, 43,
+    16, 9, 45, 17, 10, 48, 18, 10, 50, 20, 11, 52,
+    21, 11, 55, 22, 11, 57, 24, 12, 60, 25, 12, 62,
+    27, 12, 65, 28, 12, 67, 30, 12, 69, 31, 12, 72,
+    33, 12, 74, 35, 12, 76, 36, 12, 79, 38, 12, 81,
+    40, 11, 83, 41, 11, 85, 43, 11, 87, 45, 11, 89,
+    47, 10, 91, 49, 10, 92, 50, 10, 94, 52, 10, 95,
+    54, 9, 97, 56, 9, 98, 57, 9, 99, 59, 9, 100,
+    61, 9, 101, 62, 9, 102, 64, 10, 103, 66, 10, 104,
+    68, 10, 104, 69, 10, 105, 71, 11, 106, 73, 11, 106,
+    74, 12, 107, 76, 12, 107, 77, 13, 108, 79, 13, 108,
+    81, 14, 108, 82, 14, 109, 84, 15, 109, 85, 15, 109,
+    87, 16, 110, 89, 16, 110, 90, 17, 110, 92, 18, 110,
+    93, 18, 110, 95, 19, 110, 97, 19, 110, 98, 20, 110,
+    100, 21, 110, 101, 21, 110, 103, 22, 110, 105, 22, 110,
+    106, 23, 110, 108, 24, 110, 109, 24, 110, 111, 25, 110,
+    113, 25, 110, 114, 26, 110, 116, 26, 110, 117, 27, 110,
+    119, 28, 109, 120, 28, 109, 122, 29, 109, 124, 29, 109,
+    125, 30, 109, 127, 30, 108, 128, 31, 108, 130, 32, 108,
+    132, 32, 107, 133, 33, 107, 135, 33, 107, 136, 34, 106,
+    138, 34, 106, 140, 35, 105, 141, 35, 105, 143, 36, 105,
+    144, 37, 104, 146, 37, 104, 147, 38, 103, 149, 38, 103,
+    151, 39, 102, 152, 39, 102, 154, 40, 101, 155, 41, 100,
+    157, 41, 100, 159, 42, 99, 160, 42, 99, 162, 43, 98,
+    163, 44, 97, 165, 44, 96, 166, 45, 96, 168, 46, 95,
+    169, 46, 94, 171, 47, 94, 173, 48, 93, 174, 48, 92,
+    176, 49, 91, 177, 50, 90, 179, 50, 90, 180, 51, 89,
+    182, 52, 88, 183, 53, 87, 185, 53, 86, 186, 54, 85,
+    188, 55, 84, 189, 56, 83, 191, 57, 82, 192, 58, 81,
+    193, 58, 80, 195, 59, 79, 196, 60, 78, 198, 61, 77,
+    199, 62, 76, 200, 63, 75, 202, 64, 74, 203, 65, 73,
+    204, 66, 72, 206, 67, 71, 207, 68, 70, 208, 69, 69,
+    210, 70, 68, 211, 71, 67, 212, 72, 66, 213, 74, 65,
+    215, 75, 63, 216, 76, 62, 217, 77, 61, 218, 78, 60,
+    219, 80, 59, 221, 81, 58, 222, 82, 56, 223, 83, 55,
+    224, 85, 54, 225, 86, 53, 226, 87, 52, 227, 89, 51,
+    228, 90, 49, 229, 92, 48, 230, 93, 47, 231, 94, 46,
+    232, 96, 45, 233, 97, 43, 234, 99, 42, 235, 100, 41,
+    235, 102, 40, 236, 103, 38, 237, 105, 37, 238, 106, 36,
+    239, 108, 35, 239, 110, 33, 240, 111, 32, 241, 113, 31,
+    241, 115, 29, 242, 116, 28, 243, 118, 27, 243, 120, 25,
+    244, 121, 24, 245, 123, 23, 245, 125, 21, 246, 126, 20,
+    246, 128, 19, 247, 130, 18, 247, 132, 16, 248, 133, 15,
+    248, 135, 14, 248, 137, 12, 249, 139, 11, 249, 140, 10,
+    249, 142, 9, 250, 144, 8, 250, 146, 7, 250, 148, 7,
+    251, 150, 6, 251, 151, 6, 251, 153, 6, 251, 155, 6,
+    251, 157, 7, 252, 159, 7, 252, 161, 8, 252, 163, 9,
+    252, 165, 10, 252, 166, 12, 252, 168, 13, 252, 170, 15,
+    252, 172, 17, 252, 174, 18, 252, 176, 20, 252, 178, 22,
+    252, 180, 24, 251, 182, 26, 251, 184, 29, 251, 186, 31,
+    251, 188, 33, 251, 190, 35, 250, 192, 38, 250, 194, 40,
+    250, 196, 42, 250, 198, 45, 249, 199, 47, 249, 201, 50,
+    249, 203, 53, 248, 205, 55, 248, 207, 58, 247, 209, 61,
+    247, 211, 64, 246, 213, 67, 246, 215, 70, 245, 217, 73,
+    245, 219, 76, 244, 221, 79, 244, 223, 83, 244, 225, 86,
+    243, 227, 90, 243, 229, 93, 242, 230, 97, 242, 232, 101,
+    242, 234, 105, 241, 236, 109, 241, 237, 113, 241, 239, 117,
+    241, 241, 121, 242, 242, 125, 242, 244, 130, 243, 245, 134,
+    243, 246, 138, 244, 248, 142, 245, 249, 146, 246, 250, 150,
+    248, 251, 154, 249, 252, 157, 250, 253, 161, 252, 255, 164,
+];
+
+#[rustfmt::skip]
+static MAGMA: [u8; 256 * 3] = [
+    0, 0, 4, 1, 0, 5, 1, 1, 6, 1, 1, 8,
+    2, 1, 9, 2, 2, 11, 2, 2, 13, 3, 3, 15,
+    3, 3, 18, 4, 4, 20, 5, 4, 22, 6, 5, 24,
+    6, 5, 26, 7, 6, 28, 8, 7, 30, 9, 7, 32,
+    10, 8, 34, 11, 9, 36, 12, 9, 38, 13, 10, 41,
+    14, 11, 43, 16, 11, 45, 17, 12, 47, 18, 13, 49,
+    19, 13, 52, 20, 14, 54, 21, 14, 56, 22, 15, 59,
+    24, 15, 61, 25, 16, 63, 26, 16, 66, 28, 16, 68,
+    29, 17, 71, 30, 17, 73, 32, 17, 75, 33, 17, 78,
+    34, 17, 80, 36, 18, 83, 37, 18, 85, 39, 18, 88,
+    41, 17, 90, 42, 17, 92, 44, 17, 95, 45, 17, 97,
+    47, 17, 99, 49, 17, 101, 51, 16, 103, 52, 16, 105,
+    54, 16, 107, 56, 16, 108, 57, 15, 110, 59, 15, 112,
+    61, 15, 113, 63, 15, 114, 64, 15, 116, 66, 15, 117,
+    68, 15, 118, 69, 16, 119, 71, 16, 120, 73, 16, 120,
+    74, 16, 121, 76, 17, 122, 78, 17, 123, 79, 18, 123,
+    81, 18, 124, 82, 19, 124, 84, 19, 125, 86, 20, 125,
+    87, 21, 126, 89, 21, 126, 90, 22, 126, 92, 22, 127,
+    93, 23, 127, 95, 24, 127, 96, 24, 128, 98, 25, 128,
+    100, 26, 128, 101, 26, 128, 103, 27, 128, 104, 28, 129,
+    106, 28, 129, 107, 29, 129, 109, 29, 129, 110, 30, 129,
+    112, 31, 129, 114, 31, 129, 115, 32, 129, 117, 33, 129,
+    118, 33, 129, 120, 34, 129, 121, 34, 130, 123, 35, 130,
+    124, 35, 130, 126, 36, 130, 128, 37, 130, 129, 37, 129,
+    131, 38, 129, 132, 38, 129, 134, 39, 129, 136, 39, 129,
+    137, 40, 129, 139, 41, 129, 140, 41, 129, 142, 42, 129,
+    144, 42, 129, 145, 43, 129, 147, 43, 128, 148, 44, 128,
+    150, 44, 128, 152, 45, 128, 153, 45, 128, 155, 46, 127,
+    156, 46, 127, 158, 47, 127, 160, 47, 127, 161, 48, 126,
+    163, 48, 126, 165, 49, 126, 166, 49, 125, 168, 50, 125,
+    170, 51, 125, 171, 51, 124, 173, 52, 124, 174, 52, 123,
+    176, 53, 123, 178, 53, 123, 179, 54, 122, 181, 54, 122,
+    183, 55, 121, 184, 55, 121, 186, 56, 120, 188, 57, 120,
+    189, 57, 119, 191, 58, 119, 192, 58, 118, 194, 59, 117,
+    196, 60, 117, 197, 60, 116, 199, 61, 115, 200, 62, 115,
+    202, 62, 114, 204, 63, 113, 205, 64, 113, 207, 64, 112,
+    208, 65, 111, 210, 66, 111, 211, 67, 110, 213, 68, 109,
+    214, 69, 108, 216, 69, 108, 217, 70, 107, 219, 71, 106,
+    220, 72, 105, 222, 73, 104, 223, 74, 104, 224, 76, 103,
+    226, 77, 102, 227, 78, 101, 228, 79, 100, 229, 80, 100,
+    231, 82, 99, 232, 83, 98, 233, 84, 98, 234, 86, 97,
+    235, 87, 96, 236, 88, 96, 237, 90, 95, 238, 91, 94,
+    239, 93, 94, 240, 95, 94, 241, 96, 93, 242, 98, 93,
+    242, 100, 92, 243, 101, 92, 244, 103, 92, 244, 105, 92,
+    245, 107, 92, 246, 108, 92, 246, 110, 92, 247, 112, 92,
+    247, 114, 92, 248, 116, 92, 248, 118, 92, 249, 120, 93,
+    249, 121, 93, 249, 123, 93, 250, 125, 94, 250, 127, 94,
+    250, 129, 95, 251, 131, 95, 251, 133, 96, 251, 135, 97,
+    252, 137, 97, 252, 138, 98, 252, 140, 99, 252, 142, 100,
+    252, 144, 101, 253, 146, 102, 253, 148, 103, 253, 150, 104,
+    253, 152, 105, 253, 154, 106, 253, 155, 107, 254, 157, 108,
+    254, 159, 109, 254, 161, 110, 254, 163, 111, 254, 165, 113,
+    254, 167, 114, 254, 169, 115, 254, 170, 116, 254, 172, 118,
+    254, 174, 119, 254, 176, 120, 254, 178, 122, 254, 180, 123,
+    254, 182, 124, 254, 183, 126, 254, 185, 127, 254, 187, 129,
+    254, 189, 130, 254, 191, 132, 254, 193, 133, 254, 194, 135,
+    254, 196, 136, 254, 198, 138, 254, 200, 140, 254, 202, 141,
+    254, 204, 143, 254, 205, 144, 254, 207, 146, 254, 209, 148,
+    254, 211, 149, 254, 213, 151, 254, 215, 153, 254, 216, 154,
+    253, 218, 156, 253, 220, 158, 253, 222, 160, 253, 224, 161,
+    253, 226, 163, 253, 227, 165, 253, 229, 167, 253, 231, 169,
+    253, 233, 170, 253, 235, 172, 252, 236, 174, 252, 238, 176,
+    252, 240, 178, 252, 242, 180, 252, 244, 182, 252, 246, 184,
+    252, 247, 185, 252, 249, 187, 252, 251, 189, 252, 253, 191,
+];
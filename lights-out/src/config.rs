@@ -0,0 +1,77 @@
+// Persistent user configuration (~/.config/lights-out/config.json)
+
+use crate::LianliSpeed;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub lianli: LianliConfig,
+    pub msi: MsiConfig,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LianliConfig {
+    /// Per-channel settings keyed by channel index. Empty means every
+    /// channel is assumed to be fully populated.
+    pub channels: BTreeMap<u8, LianliChannelConfig>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LianliChannelConfig {
+    /// Number of fans daisy-chained on this channel (0 = unused)
+    pub fans: u8,
+    /// Animation speed used when no speed was set on the command line
+    pub speed: Option<LianliSpeed>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MsiConfig {
+    /// Factor the daemon multiplies the temperature it sends by (unset = 1)
+    pub fan_speed_multiplier: Option<f32>,
+}
+
+/// Directory holding lights-out configuration ($XDG_CONFIG_HOME/lights-out)
+pub fn config_dir() -> PathBuf {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_else(|| PathBuf::from("/etc"));
+    base.join("lights-out")
+}
+
+impl Config {
+    pub fn path() -> PathBuf {
+        config_dir().join("config.json")
+    }
+
+    /// Load the config file, or defaults if it doesn't exist yet
+    pub fn load() -> Result<Config> {
+        let path = Config::path();
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Config::path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(&path, content + "\n")
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
@@ -1,13 +1,32 @@
-use anyhow::{Context, Result};
-use clap::{Parser, Subcommand, ValueEnum};
-use hidapi::{HidApi, HidDevice};
+mod color;
+mod config;
+mod metrics;
+mod openrgb;
+mod state;
+mod systemd;
+mod wallpaper;
+
+use anyhow::{anyhow, Context, Result};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use color::{
+    colormap_lookup, extract_zone_colors, gradient_at, hsv_to_rgb, kelvin_to_rgb, lerp_color, Rgb,
+};
+use config::{config_dir, Config};
+use hidapi::{DeviceInfo, HidApi, HidDevice, HidError};
 use i2cdev::core::I2CDevice;
-use i2cdev::linux::LinuxI2CDevice;
+use i2cdev::linux::{LinuxI2CDevice, LinuxI2CError};
+use serde::{Deserialize, Serialize};
+use state::{data_dir, write_atomic, GpuState, LianliChannelState, LianliState};
+use std::fmt;
 use std::fs;
+use std::io;
+use std::num::ParseIntError;
 use std::path::Path;
+use std::process::ExitCode;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use wallpaper::WallpaperWatcher;
 
 // MSI MPG CORELIQUID
 mod msi {
@@ -15,10 +34,38 @@ mod msi {
     pub const PID: u16 = 0xb130;
     pub const FEATURE_REPORT_ID: u8 = 0x52;
     pub const MAX_DATA_LEN: usize = 185;
+    // Buffer size used when probing reports of unknown length
+    pub const MAX_REPORT_PROBE_LEN: usize = 512;
     pub const HID_REPORT_LEN: usize = 65; // 64 bytes + report ID
     pub const CMD_PREFIX: u8 = 0xD0;
     pub const CMD_LCD_DISABLE: u8 = 0x7F;
+
+    // LED zone modes (from OpenRGB MSIMysticLightCommon.h)
     pub const LED_MODE_DISABLE: u8 = 0;
+    pub const LED_MODE_STATIC: u8 = 1;
+    pub const LED_MODE_BREATHING: u8 = 2;
+    pub const LED_MODE_FLASHING: u8 = 3;
+    pub const LED_MODE_DOUBLE_FLASHING: u8 = 4;
+    pub const LED_MODE_RAINBOW: u8 = 25;
+
+    // Zone layout relative to each LED_OFFSETS entry (OpenRGB ZoneData):
+    // mode, R, G, B, speed/brightness flags, R2, G2, B2, color flags, padding
+    pub const ZONE_COLOR: usize = 1;
+    pub const ZONE_SPEED_BRIGHTNESS: usize = 4;
+    pub const ZONE_COLOR2: usize = 5;
+    pub const ZONE_COLOR_FLAGS: usize = 8;
+    pub const ZONE_LEN: usize = 10;
+    // JRAINBOW zones carry an extra LED count byte after the padding
+    pub const RAINBOW_ZONE_OFFSETS: &[usize] = &[31, 42];
+    pub const SAVE_DATA_OFFSET: usize = 184;
+    pub const LED_BRIGHTNESS_MAX: u8 = 10; // stored in bits 2-6
+    pub const LED_BRIGHTNESS_MASK: u8 = 0x7C;
+    pub const LED_SPEED_MEDIUM: u8 = 1; // stored in bits 0-1
+    pub const LED_SPEED_HIGH: u8 = 2;
+    pub const LED_SPEED_MASK: u8 = 0x03;
+    pub const ALERT_HYSTERESIS_C: i32 = 5;
+    pub const MAX_SANE_MILLIDEGREES: i64 = 120_000;
+    pub const POST_COMMAND_WARN_MS: u64 = 1000;
 
     // Fan mode commands
     pub const CMD_FAN_MODE_1: u8 = 0x40;
@@ -27,19 +74,90 @@ mod msi {
     // CPU status command (for temperature reporting)
     pub const CMD_CPU_STATUS: u8 = 0x85;
 
+    // Readback commands (from liquidctl's MSI driver); the reply echoes the
+    // prefix and command byte
+    pub const CMD_GET_FAN_STATUS: u8 = 0x31;
+    pub const CMD_GET_FAN_CONFIG: u8 = 0x32;
+    pub const READ_TIMEOUT_MS: i32 = 1000;
+
+    // Fan status reply: little-endian u16 speed and duty per fan
+    pub const FAN_NAMES: &[&str] = &["Fan 1", "Fan 2", "Fan 3", "Water block fan", "Pump"];
+    pub const FAN_RPM_OFFSETS: &[usize] = &[2, 4, 6, 8, 10];
+    pub const FAN_DUTY_OFFSETS: &[usize] = &[22, 24, 26, 28, 30];
+
     // Fan mode offsets in the command buffer (after cmd prefix and command byte)
     pub const FAN_MODE_OFFSETS: &[usize] = &[2, 10, 18, 26, 34];
 
-    // Daemon polling interval in seconds
-    pub const DAEMON_INTERVAL_SECS: u64 = 2;
+    // Each mode byte is followed by the channel's curve: 7 duty points (%) in
+    // 0x40/0x32 and 7 temperature points (°C) in 0x41/0x33. There is no separate
+    // pump speed command; the pump is the last channel and takes a curve like the
+    // fans, which in the customize mode is used as given.
+    pub const CMD_GET_FAN_TEMP_CONFIG: u8 = 0x33;
+    pub const FAN_MODE_CUSTOMIZE: u8 = 3;
+    pub const FAN_CURVE_POINTS: usize = 7;
+    pub const PUMP_CHANNEL: usize = 4; // index into FAN_MODE_OFFSETS
+    pub const PUMP_DUTY_MIN: u8 = 60; // `pump-speed set 0`; the pump is never stopped
+
+    // Daemon stop-flag polling granularity while sleeping
+    pub const DAEMON_POLL_MS: u64 = 100;
+    pub const RECORD_MAGIC: &[u8] = b"LOMSIREC"; // `msi dump --record` file header
+    pub const DAEMON_STOP_TIMEOUT_MS: u64 = 5000; // `daemon stop` wait for exit
+    pub const MAX_TEMP_AVERAGE_SAMPLES: usize = 64; // --cpu-temp-average upper bound
+
+    // `msi fan speed-multiplier` bounds; the protocol has no Smart mode tuning,
+    // so the daemon scales the temperature it sends instead
+    pub const MIN_FAN_SPEED_MULTIPLIER: f32 = 0.5;
+    pub const MAX_FAN_SPEED_MULTIPLIER: f32 = 2.0;
+    pub const TRANSITION_FRAME_MS: u64 = 33; // ~30 Hz `profile apply --transition fade`
 
     pub const LED_OFFSETS: &[usize] = &[
         1, 11, 21, 31, 42, 53, 74, 84, 94, 104, 114, 124, 134, 144, 154, 164, 174,
     ];
+    // (name, description) of each LED_OFFSETS entry, following the zone order of
+    // OpenRGB's MSIMysticLight185 FeaturePacket_185. The CORELIQUID reuses the
+    // motherboard layout; which zones actually light on a given cooler can be
+    // checked with `msi led-test`.
+    pub static ZONE_MAP: &[(&str, &str)] = &[
+        ("JRGB1", "12V RGB header 1"),
+        ("JPIPE1", "Pipe/heatsink LEDs 1"),
+        ("JPIPE2", "Pipe/heatsink LEDs 2"),
+        ("JRAINBOW1", "5V addressable header 1 (has LED count)"),
+        ("JRAINBOW2", "5V addressable header 2 (has LED count)"),
+        ("JCORSAIR", "Corsair-compatible header"),
+        ("Onboard LED 0", "Onboard LED 0"),
+        ("Onboard LED 1", "Onboard LED 1"),
+        ("Onboard LED 2", "Onboard LED 2"),
+        ("Onboard LED 3", "Onboard LED 3"),
+        ("Onboard LED 4", "Onboard LED 4"),
+        ("Onboard LED 5", "Onboard LED 5"),
+        ("Onboard LED 6", "Onboard LED 6"),
+        ("Onboard LED 7", "Onboard LED 7"),
+        ("Onboard LED 8", "Onboard LED 8"),
+        ("Onboard LED 9", "Onboard LED 9"),
+        ("Onboard LED 10", "Onboard LED 10"),
+    ];
+    // Zone indices by location on the cooler. The pump head's own ring sits on
+    // the onboard LED zones and the radiator fans are chained off the
+    // addressable headers; verify on your unit with `msi led-test`.
+    pub const PUMP_HEAD_ZONES: &[usize] = &[6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+    pub const RADIATOR_FAN_ZONES: &[usize] = &[3, 4];
+
+    // Known USB releases (bcdDevice) as (release, notes, works with lights-out),
+    // shown by `firmware-update-check`, e.g. ("1.08", "Tested on ...", true).
+    // Only add entries with a source for the notes. Keep msi-firmware.json in
+    // sync: `--online` reads it from FIRMWARE_DB_URL as an array of
+    // {"version", "notes", "compatible"} objects.
+    pub const FIRMWARE_DB: &[(&str, &str, bool)] = &[];
+    pub const FIRMWARE_DB_URL: &str = concat!(
+        "https://raw.githubusercontent.com/benwbooth/lights-out/",
+        "master/lights-out/msi-firmware.json"
+    );
+    pub const FIRMWARE_DB_TIMEOUT_SECS: u64 = 10;
 }
 
 /// Fan modes for MSI CORELIQUID AIO cooler
-#[derive(Debug, Clone, Copy, ValueEnum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum FanMode {
     /// Silent mode - quietest, lower cooling
     Silent = 0,
@@ -53,35 +171,434 @@ pub enum FanMode {
     Smart = 5,
 }
 
+impl FanMode {
+    fn from_byte(b: u8) -> Option<FanMode> {
+        FanMode::value_variants()
+            .iter()
+            .copied()
+            .find(|&mode| mode as u8 == b)
+    }
+}
+
+/// Set mode and primary color of every zone in an MSI LED feature report buffer
+///
+/// Brightness is raised to maximum; the speed bits are preserved.
+fn msi_fill_zones(buf: &mut [u8; msi::MAX_DATA_LEN], mode: u8, rgb: Rgb) {
+    for &offset in msi::LED_OFFSETS {
+        msi_set_zone(buf, offset, mode, rgb);
+    }
+}
+
+/// Set mode and primary color of the zone starting at `offset`
+fn msi_set_zone(buf: &mut [u8; msi::MAX_DATA_LEN], offset: usize, mode: u8, rgb: Rgb) {
+    buf[offset] = mode;
+    buf[offset + msi::ZONE_COLOR] = rgb.r;
+    buf[offset + msi::ZONE_COLOR + 1] = rgb.g;
+    buf[offset + msi::ZONE_COLOR + 2] = rgb.b;
+    let flags = &mut buf[offset + msi::ZONE_SPEED_BRIGHTNESS];
+    *flags = (*flags & !msi::LED_BRIGHTNESS_MASK) | (msi::LED_BRIGHTNESS_MAX << 2);
+}
+
+/// Build an LED feature report with every zone in `mode`/`rgb` at full brightness
+const fn msi_preset_report(mode: u8, rgb: Rgb) -> [u8; msi::MAX_DATA_LEN] {
+    let mut buf = [0u8; msi::MAX_DATA_LEN];
+    buf[0] = msi::FEATURE_REPORT_ID;
+    let mut i = 0;
+    while i < msi::LED_OFFSETS.len() {
+        let offset = msi::LED_OFFSETS[i];
+        buf[offset] = mode;
+        buf[offset + msi::ZONE_COLOR] = rgb.r;
+        buf[offset + msi::ZONE_COLOR + 1] = rgb.g;
+        buf[offset + msi::ZONE_COLOR + 2] = rgb.b;
+        buf[offset + msi::ZONE_SPEED_BRIGHTNESS] =
+            (msi::LED_BRIGHTNESS_MAX << 2) | msi::LED_SPEED_MEDIUM;
+        i += 1;
+    }
+    buf
+}
+
+// Built-in presets, generated from the zone layout. They are not captures of
+// MSI Center's profiles and only set the zone modes and colors.
+const LED_PRESET_GAMING: [u8; msi::MAX_DATA_LEN] =
+    msi_preset_report(msi::LED_MODE_STATIC, Rgb::RED);
+const LED_PRESET_SILENT: [u8; msi::MAX_DATA_LEN] =
+    msi_preset_report(msi::LED_MODE_STATIC, Rgb::BLUE);
+const LED_PRESET_DEFAULT: [u8; msi::MAX_DATA_LEN] =
+    msi_preset_report(msi::LED_MODE_RAINBOW, Rgb::WHITE);
+
+/// Built-in LED presets
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum LedPreset {
+    /// Static red
+    Gaming,
+    /// Static blue
+    Silent,
+    /// Rainbow
+    Default,
+}
+
+impl LedPreset {
+    fn report(self) -> &'static [u8; msi::MAX_DATA_LEN] {
+        match self {
+            LedPreset::Gaming => &LED_PRESET_GAMING,
+            LedPreset::Silent => &LED_PRESET_SILENT,
+            LedPreset::Default => &LED_PRESET_DEFAULT,
+        }
+    }
+
+    /// Primary color of the profile, which fades end on before the final mode is set
+    fn color(self) -> Rgb {
+        match self {
+            LedPreset::Gaming => Rgb::RED,
+            LedPreset::Silent => Rgb::BLUE,
+            LedPreset::Default => Rgb::WHITE,
+        }
+    }
+}
+
+/// How `profile apply` moves from the current LEDs to the profile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Transition {
+    /// Switch in one write
+    Instant,
+    /// Blend every zone from its current color over --duration
+    Fade,
+}
+
+/// LED zone modes for MSI CORELIQUID (Mystic Light protocol)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[repr(u8)]
+pub enum MsiLedMode {
+    /// LEDs off
+    Disable = msi::LED_MODE_DISABLE,
+    /// Solid color
+    Static = msi::LED_MODE_STATIC,
+    /// Fade in and out
+    Breathing = msi::LED_MODE_BREATHING,
+    /// On/off blinking
+    Flashing = msi::LED_MODE_FLASHING,
+    /// Two quick blinks, then pause
+    DoubleFlashing = msi::LED_MODE_DOUBLE_FLASHING,
+    /// Random lightning flashes
+    Lightning = 5,
+    /// MSI marquee
+    MsiMarquee = 6,
+    /// Meteor trail
+    Meteor = 7,
+    /// Water drop
+    WaterDrop = 8,
+    /// MSI rainbow
+    MsiRainbow = 9,
+    /// Music reactive: pop
+    Pop = 10,
+    /// Music reactive: rap
+    Rap = 11,
+    /// Music reactive: jazz
+    Jazz = 12,
+    /// Music reactive: play
+    Play = 13,
+    /// Music reactive: movie
+    Movie = 14,
+    /// Color ring
+    ColorRing = 15,
+    /// Planetary
+    Planetary = 16,
+    /// Double meteor
+    DoubleMeteor = 17,
+    /// Energy
+    Energy = 18,
+    /// Blink
+    Blink = 19,
+    /// Clock
+    Clock = 20,
+    /// Color pulse
+    ColorPulse = 21,
+    /// Color shift
+    ColorShift = 22,
+    /// Color wave
+    ColorWave = 23,
+    /// Marquee
+    Marquee = 24,
+    /// Rainbow
+    Rainbow = msi::LED_MODE_RAINBOW,
+    /// Rainbow wave
+    RainbowWave = 26,
+    /// Visor
+    Visor = 27,
+    /// JRainbow
+    Jrainbow = 28,
+    /// Rainbow flashing
+    RainbowFlashing = 29,
+    /// Rainbow double flashing
+    RainbowDoubleFlashing = 30,
+    /// Random
+    Random = 31,
+}
+
+impl MsiLedMode {
+    fn from_byte(b: u8) -> Option<MsiLedMode> {
+        MsiLedMode::value_variants()
+            .iter()
+            .copied()
+            .find(|&mode| mode as u8 == b)
+    }
+}
+
+/// CLI name of a mode byte, or the raw value for modes we don't know
+fn msi_led_mode_name(b: u8) -> String {
+    MsiLedMode::from_byte(b)
+        .and_then(|mode| mode.to_possible_value())
+        .map_or_else(|| format!("mode {}", b), |v| v.get_name().to_string())
+}
+
+/// Parse an MSI LED mode by name ("breathing") or raw mode byte ("2")
+fn parse_msi_led_mode(s: &str) -> Result<u8> {
+    if let Ok(mode) = MsiLedMode::from_str(s, true) {
+        return Ok(mode as u8);
+    }
+    parse_u8(s).with_context(|| format!("Unknown MSI LED mode: {}", s))
+}
+
+fn parse_fan_speed_multiplier(s: &str) -> Result<f32> {
+    let multiplier: f32 = s
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid multiplier: {}", s))?;
+    if !(msi::MIN_FAN_SPEED_MULTIPLIER..=msi::MAX_FAN_SPEED_MULTIPLIER).contains(&multiplier) {
+        anyhow::bail!(
+            "Multiplier must be {}-{}",
+            msi::MIN_FAN_SPEED_MULTIPLIER,
+            msi::MAX_FAN_SPEED_MULTIPLIER
+        );
+    }
+    Ok(multiplier)
+}
+
+// HID usage pages/usages (from the USB HID Usage Tables)
+mod hid {
+    pub const USAGE_PAGE_GENERIC_DESKTOP: u16 = 0x0001;
+    pub const USAGE_PAGE_VENDOR_DEFINED: u16 = 0xFF00; // 0xFF00-0xFFFF
+    pub const USAGE_MOUSE: u16 = 0x02;
+    pub const USAGE_KEYBOARD: u16 = 0x06;
+}
+
 // LianLi UNI FAN AL V2 (from OpenRGB LianLiUniHubALController)
 mod lianli {
+    use crate::color::Rgb;
+
     pub const VID: u16 = 0x0cf2;
     pub const PID: u16 = 0xa104;
     pub const TRANSACTION_ID: u8 = 0xe0;
     pub const PACKET_SIZE: usize = 65; // Standard packet size
-    pub const COLOR_PACKET_SIZE: usize = 146; // Color data packet
 
     // Commit action command format: transaction_id, 0x10 + fan_or_edge + (channel*2), mode, speed, direction, brightness
     pub const MODE_STATIC: u8 = 0x01;
+    pub const MODE_BREATHING: u8 = 0x02;
+    pub const MODE_RAINBOW_MORPH: u8 = 0x04;
+    pub const MODE_RAINBOW: u8 = 0x05;
     pub const SPEED_VERY_SLOW: u8 = 0x02;
+    pub const SPEED_SLOW: u8 = 0x01;
+    pub const SPEED_MEDIUM: u8 = 0x00;
+    pub const SPEED_FAST: u8 = 0xFF;
+    pub const SPEED_VERY_FAST: u8 = 0xFE;
     pub const DIRECTION_LEFT_TO_RIGHT: u8 = 0x00;
+    pub const BRIGHTNESS_FULL: u8 = 0x00; // 100% brightness
     pub const BRIGHTNESS_OFF: u8 = 0x08; // 0% brightness
+    pub const PACKET_DELAY_MS: u64 = 20; // Delay between packets
+    pub const READ_TIMEOUT_MS: i32 = 100; // The hub normally doesn't answer
+    pub const IDENTIFY_HALF_PERIOD_MS: u64 = 250; // 2 Hz white/off flash
+
+    // Software chase step per `color-animation --speed` 1-5; every step also
+    // costs 4 packets (80 ms) per channel, which caps the fastest speeds
+    pub const CHASE_STEP_MS: [u64; 5] = [400, 250, 150, 80, 40];
+    pub const CHASE_DEFAULT_LEVEL: u8 = 3;
+
+    // Packet registers, offset by channel * 2
+    pub const REG_FAN_COMMIT: u8 = 0x10;
+    pub const REG_EDGE_COMMIT: u8 = 0x11;
+    pub const REG_FAN_COLOR: u8 = 0x30;
+    pub const REG_EDGE_COLOR: u8 = 0x31;
 
     pub const NUM_CHANNELS: u8 = 4;
+    pub const MAX_FANS_PER_CHANNEL: u8 = 4;
+    pub const FAN_LEDS_PER_FAN: usize = 8; // Inner (fan blade) ring
+    pub const EDGE_LEDS_PER_FAN: usize = 12; // Outer (edge) ring
+
+    // Gradient stops (position 0.0-1.0, color) for `lianli color-scene`, spread
+    // across all fans in channel order
+    pub const SCENE_SUNSET: [(f32, Rgb); 5] = [
+        (0.0, Rgb::new(40, 0, 80)),
+        (0.3, Rgb::new(200, 30, 90)),
+        (0.55, Rgb::new(255, 80, 20)),
+        (0.8, Rgb::new(255, 150, 0)),
+        (1.0, Rgb::new(255, 210, 80)),
+    ];
+    pub const SCENE_OCEAN: [(f32, Rgb); 4] = [
+        (0.0, Rgb::new(0, 20, 80)),
+        (0.4, Rgb::new(0, 80, 180)),
+        (0.75, Rgb::new(0, 170, 200)),
+        (1.0, Rgb::new(120, 230, 220)),
+    ];
+    pub const SCENE_FOREST: [(f32, Rgb); 4] = [
+        (0.0, Rgb::new(10, 60, 10)),
+        (0.35, Rgb::new(30, 140, 20)),
+        (0.7, Rgb::new(120, 200, 30)),
+        (1.0, Rgb::new(90, 60, 10)),
+    ];
+    pub const SCENE_AURORA: [(f32, Rgb); 5] = [
+        (0.0, Rgb::new(0, 255, 120)),
+        (0.3, Rgb::new(0, 200, 200)),
+        (0.55, Rgb::new(40, 80, 255)),
+        (0.8, Rgb::new(150, 0, 255)),
+        (1.0, Rgb::new(255, 0, 160)),
+    ];
+    pub const SCENE_EMBER: [(f32, Rgb); 4] = [
+        (0.0, Rgb::new(120, 0, 0)),
+        (0.4, Rgb::new(255, 30, 0)),
+        (0.75, Rgb::new(255, 110, 0)),
+        (1.0, Rgb::new(255, 200, 40)),
+    ];
+    pub const SCENE_GLACIER: [(f32, Rgb); 4] = [
+        (0.0, Rgb::new(255, 255, 255)),
+        (0.35, Rgb::new(170, 220, 255)),
+        (0.7, Rgb::new(60, 140, 255)),
+        (1.0, Rgb::new(20, 40, 160)),
+    ];
+
+    /// Color packet length for `fans` fans with `leds_per_fan` LEDs each (2-byte header + RGB)
+    pub fn color_packet_len(fans: u8, leds_per_fan: usize) -> usize {
+        2 + fans as usize * leds_per_fan * 3
+    }
+}
+
+/// LianLi hub animation speeds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[repr(u8)]
+pub enum LianliSpeed {
+    VerySlow = lianli::SPEED_VERY_SLOW,
+    Slow = lianli::SPEED_SLOW,
+    Medium = lianli::SPEED_MEDIUM,
+    Fast = lianli::SPEED_FAST,
+    VeryFast = lianli::SPEED_VERY_FAST,
+}
+
+impl LianliSpeed {
+    /// Matching `color-animation --speed` level (1 = slowest, 5 = fastest)
+    fn chase_level(self) -> u8 {
+        match self {
+            LianliSpeed::VerySlow => 1,
+            LianliSpeed::Slow => 2,
+            LianliSpeed::Medium => 3,
+            LianliSpeed::Fast => 4,
+            LianliSpeed::VeryFast => 5,
+        }
+    }
+}
+
+/// LianLi hub effects that can be set per channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[repr(u8)]
+enum LianliEffect {
+    Static = lianli::MODE_STATIC,
+    Breathing = lianli::MODE_BREATHING,
+    /// Whole channel cycles through the hues (color is ignored)
+    RainbowMorph = lianli::MODE_RAINBOW_MORPH,
+    /// Hues move around the fans (color is ignored)
+    Rainbow = lianli::MODE_RAINBOW,
+}
+
+/// Packets `lianli hex-dump` can show
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LianliPacketType {
+    /// Fan and edge ring color packets
+    Color,
+    /// Fan and edge commit packets (mode, speed, direction, brightness)
+    Commit,
+}
+
+/// Animations the hub has no native mode for, rendered per LED on the host
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LianliAnimation {
+    /// One lit LED running around each ring, in step on channels at the same speed
+    Chase,
+}
+
+/// Effect and color for one hub channel
+///
+/// Each channel has its own commit registers, so channels can run different
+/// effects side by side.
+#[derive(Debug, Clone, Copy)]
+struct ChannelConfig {
+    channel: u8,
+    effect: LianliEffect,
+    color: Rgb,
+}
+
+impl ChannelConfig {
+    /// Commit state for this channel; speed and direction are kept from `cached`
+    fn state(&self, cached: LianliChannelState) -> LianliChannelState {
+        LianliChannelState {
+            mode: self.effect as u8,
+            brightness: lianli::BRIGHTNESS_FULL,
+            fan_color: self.color,
+            edge_color: self.color,
+            ..cached
+        }
+    }
 }
 
 // ASUS TUF Gaming GPU with ENE SMBus RGB controller
 mod gpu {
     // ENE SMBus protocol (from OpenRGB ENESMBusController)
     pub const ENE_I2C_ADDR: u16 = 0x67;
+    pub const ENE_REG_DEVICE_NAME: u16 = 0x1000; // 16-byte ASCII version string
+    pub const ENE_DEVICE_NAME_LEN: u16 = 16;
+    pub const ENE_REG_COLORS_EFFECT: u16 = 0x8010;
+    pub const ENE_REG_COLORS_EFFECT_V2: u16 = 0x8160;
+    pub const ENE_REG_COLORS_DIRECT: u16 = 0x8000;
+    pub const ENE_REG_DIRECT: u16 = 0x8020;
     pub const ENE_REG_MODE: u16 = 0x8021;
+    pub const ENE_REG_SPEED: u16 = 0x8022;
+    pub const ENE_REG_DIRECTION: u16 = 0x8023;
     pub const ENE_REG_APPLY: u16 = 0x80A0;
+    pub const ENE_DUMP_START: u16 = 0x8000;
+    // tracefs mount points, newest first
+    pub const TRACEFS_PATHS: &[&str] = &["/sys/kernel/tracing", "/sys/kernel/debug/tracing"];
+    pub const ENE_DUMP_LEN: u16 = 0x100;
+    pub const ENE_REG_CONFIG_TABLE: u16 = 0x1C00;
+    pub const ENE_CONFIG_LED_COUNT: u16 = 0x02; // Offset into the config table
     pub const ENE_MODE_OFF: u8 = 0x00;
+    pub const ENE_MODE_STATIC: u8 = 0x01;
+    pub const ENE_MODE_BREATHING: u8 = 0x02;
+    pub const ENE_MODE_FLASHING: u8 = 0x03;
+    pub const ENE_MODE_SPECTRUM_CYCLE: u8 = 0x04;
+    pub const ENE_MODE_RAINBOW: u8 = 0x05;
     pub const ENE_APPLY_VAL: u8 = 0x01;
+    pub const I2C_SCAN_FIRST: u16 = 0x08; // i2cdetect's default range
+    pub const I2C_SCAN_LAST: u16 = 0x77;
+    // Addresses of controllers seen on GPU i2c buses
+    pub const KNOWN_I2C_DEVICES: &[(u16, &str)] = &[
+        (0x50, "DDC/EDID EEPROM"),
+        (0x58, "ITE IT8291 RGB controller"),
+        (ENE_I2C_ADDR, "ENE SMBus RGB controller"),
+    ];
+    pub const HEALTH_CHECK_MAX_ERRORS_SHOWN: u32 = 5;
+    pub const HEALTH_CHECK_REPEATED_ERRORS: u32 = 3; // Consecutive failures that suggest hardware
+
+    // `gpu disable-on-boot install` (templates in lights-out/systemd/)
+    pub const BOOT_GENERATOR: &str = include_str!("../systemd/lights-out-gpu-generator");
+    pub const BOOT_UNIT: &str = include_str!("../systemd/lights-out-gpu.service.in");
+    pub const BOOT_UDEV_RULE: &str = include_str!("../systemd/lights-out-gpu.rules");
+    pub const BOOT_GENERATOR_PATH: &str = "/etc/systemd/system-generators/lights-out-gpu-generator";
+    pub const BOOT_UDEV_RULE_PATH: &str = "/etc/udev/rules.d/90-lights-out-gpu.rules";
+    // /dev/i2c-* only appears once i2c-dev is loaded
+    pub const BOOT_MODULES_LOAD_PATH: &str = "/etc/modules-load.d/lights-out-gpu.conf";
 
     // SMBus commands
     pub const SMBUS_CMD_ADDR: u8 = 0x00; // Register address selector (word)
     pub const SMBUS_CMD_DATA: u8 = 0x01; // Data write (byte)
+    pub const SMBUS_CMD_READ: u8 = 0x81; // Data read (byte)
 
     // Byte-swap for ENE protocol (little-endian on SMBus)
     pub fn swap_bytes(val: u16) -> u16 {
@@ -89,10 +606,194 @@ mod gpu {
     }
 }
 
+/// GPU RGB controller families
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GpuProtocol {
+    /// Probe the bus with `gpu_detect_controller`
+    Auto,
+    /// ENE SMBus, first-generation register layout
+    #[value(name = "ene-30")]
+    Ene30,
+    /// ENE SMBus, AUMA0/AUDA0 register layout
+    #[value(name = "ene-40")]
+    Ene40,
+    /// ITE IT8291
+    Ite,
+    /// Nuvoton NCT3011T
+    Nuvoton,
+}
+
+/// Preset gradients for `lianli color-scene`
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ColorScene {
+    /// Violet through pink and orange to gold
+    Sunset,
+    /// Deep blue to turquoise
+    Ocean,
+    /// Dark to light green, ending in bark brown
+    Forest,
+    /// Green, cyan, blue and violet northern lights
+    Aurora,
+    /// Glowing red to yellow embers
+    Ember,
+    /// White ice fading into deep blue
+    Glacier,
+}
+
+impl ColorScene {
+    fn stops(self) -> &'static [(f32, Rgb)] {
+        match self {
+            ColorScene::Sunset => &lianli::SCENE_SUNSET,
+            ColorScene::Ocean => &lianli::SCENE_OCEAN,
+            ColorScene::Forest => &lianli::SCENE_FOREST,
+            ColorScene::Aurora => &lianli::SCENE_AURORA,
+            ColorScene::Ember => &lianli::SCENE_EMBER,
+            ColorScene::Glacier => &lianli::SCENE_GLACIER,
+        }
+    }
+}
+
+/// ENE controller effect modes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[repr(u8)]
+pub enum GpuLedMode {
+    Off = gpu::ENE_MODE_OFF,
+    Static = gpu::ENE_MODE_STATIC,
+    Breathing = gpu::ENE_MODE_BREATHING,
+    #[value(alias = "blinking")]
+    Flashing = gpu::ENE_MODE_FLASHING,
+    SpectrumCycle = gpu::ENE_MODE_SPECTRUM_CYCLE,
+    Rainbow = gpu::ENE_MODE_RAINBOW,
+}
+
+impl GpuLedMode {
+    /// Whether the mode uses the per-LED color registers
+    fn takes_color(self) -> bool {
+        matches!(self, GpuLedMode::Static | GpuLedMode::Breathing)
+    }
+}
+
+/// Errors grouped by the process exit code they map to
+///
+/// Exit codes: 0 = success, 1 = device not found, 2 = permission error,
+/// 3 = I/O error, 4 = protocol error, 5 = invalid arguments/config.
+#[derive(Debug)]
+enum LightsOutError {
+    DeviceNotFound(anyhow::Error),
+    Permission(anyhow::Error),
+    Io(anyhow::Error),
+    Protocol(anyhow::Error),
+    Config(anyhow::Error),
+}
+
+impl LightsOutError {
+    fn exit_code(&self) -> u8 {
+        match self {
+            LightsOutError::DeviceNotFound(_) => 1,
+            LightsOutError::Permission(_) => 2,
+            LightsOutError::Io(_) => 3,
+            LightsOutError::Protocol(_) => 4,
+            LightsOutError::Config(_) => 5,
+        }
+    }
+
+    fn inner(&self) -> &anyhow::Error {
+        match self {
+            LightsOutError::DeviceNotFound(e)
+            | LightsOutError::Permission(e)
+            | LightsOutError::Io(e)
+            | LightsOutError::Protocol(e)
+            | LightsOutError::Config(e) => e,
+        }
+    }
+
+    /// Categorize an error by the first recognizable cause in its chain
+    ///
+    /// Anything unrecognized is treated as an I/O error.
+    fn classify(err: anyhow::Error) -> Self {
+        let err = match err.downcast::<LightsOutError>() {
+            Ok(e) => return e,
+            Err(err) => err,
+        };
+        let wrap = err
+            .chain()
+            .find_map(Self::category_of)
+            .unwrap_or(LightsOutError::Io);
+        wrap(err)
+    }
+
+    fn category_of(
+        cause: &(dyn std::error::Error + 'static),
+    ) -> Option<fn(anyhow::Error) -> LightsOutError> {
+        if let Some(e) = cause.downcast_ref::<LightsOutError>() {
+            return Some(match e {
+                LightsOutError::DeviceNotFound(_) => LightsOutError::DeviceNotFound,
+                LightsOutError::Permission(_) => LightsOutError::Permission,
+                LightsOutError::Io(_) => LightsOutError::Io,
+                LightsOutError::Protocol(_) => LightsOutError::Protocol,
+                LightsOutError::Config(_) => LightsOutError::Config,
+            });
+        }
+        if let Some(e) = cause.downcast_ref::<io::Error>() {
+            return Some(Self::category_of_io(e.kind()));
+        }
+        if let Some(e) = cause.downcast_ref::<LinuxI2CError>() {
+            let kind = match e {
+                LinuxI2CError::Errno(errno) => io::Error::from_raw_os_error(*errno).kind(),
+                LinuxI2CError::Io(e) => e.kind(),
+            };
+            return Some(Self::category_of_io(kind));
+        }
+        if let Some(e) = cause.downcast_ref::<HidError>() {
+            // hidapi only reports errno as text
+            return Some(match e {
+                HidError::HidApiError { message } if message.contains("Permission denied") => {
+                    LightsOutError::Permission
+                }
+                _ => LightsOutError::Io,
+            });
+        }
+        if cause.is::<ParseIntError>() {
+            return Some(LightsOutError::Protocol);
+        }
+        if cause.is::<serde_json::Error>() {
+            return Some(LightsOutError::Config);
+        }
+        None
+    }
+
+    fn category_of_io(kind: io::ErrorKind) -> fn(anyhow::Error) -> LightsOutError {
+        match kind {
+            io::ErrorKind::NotFound => LightsOutError::DeviceNotFound,
+            io::ErrorKind::PermissionDenied => LightsOutError::Permission,
+            _ => LightsOutError::Io,
+        }
+    }
+}
+
+impl fmt::Display for LightsOutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self.inner(), f)
+    }
+}
+
+impl std::error::Error for LightsOutError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.inner().source()
+    }
+}
+
 #[derive(Parser)]
 #[command(name = "ledctl")]
 #[command(about = "Control RGB LEDs on various PC components")]
+#[command(
+    after_help = "Exit codes: 0 = success, 1 = device not found, 2 = permission error, \
+                  3 = I/O error, 4 = protocol error, 5 = invalid arguments/config"
+)]
 struct Cli {
+    /// Print extra debugging output (e.g. raw HID packets)
+    #[arg(short, long, global = true)]
+    verbose: bool,
     #[command(subcommand)]
     command: Commands,
 }
@@ -101,12 +802,31 @@ struct Cli {
 enum Commands {
     /// Turn off all LEDs on all supported devices
     Off,
-    /// Turn off MSI CORELIQUID cooler LEDs and LCD
-    Msi,
-    /// Turn off LianLi UNI FAN AL V2 LEDs
-    Lianli,
-    /// Turn off ASUS TUF Gaming GPU LEDs (via i2c)
-    Gpu,
+    /// Turn off MSI CORELIQUID cooler LEDs and LCD (or run an MSI subcommand)
+    Msi {
+        #[command(subcommand)]
+        command: Option<MsiCommands>,
+        /// Which cooler to use when several are connected (see `msi devices`)
+        #[arg(long, global = true, default_value_t = 0)]
+        device_index: usize,
+    },
+    /// Turn off LianLi UNI FAN AL V2 LEDs (or run a LianLi subcommand)
+    Lianli {
+        #[command(subcommand)]
+        command: Option<LianliCommands>,
+    },
+    /// Turn off ASUS TUF Gaming GPU LEDs via i2c (or run a GPU subcommand)
+    Gpu {
+        #[command(subcommand)]
+        command: Option<GpuCommands>,
+        /// If the ENE i2c controller can't be used, turn off ASUS Aura LEDs
+        /// exposed through the sysfs LED class instead
+        #[arg(long)]
+        allow_fallback: bool,
+        /// RGB controller type on the GPU
+        #[arg(long, value_enum, default_value_t = GpuProtocol::Auto)]
+        protocol: GpuProtocol,
+    },
     /// Set MSI CORELIQUID cooler fan mode
     Fan {
         /// Fan mode to set
@@ -114,299 +834,6370 @@ enum Commands {
         mode: FanMode,
     },
     /// Run temperature monitoring daemon for MSI CORELIQUID (sends CPU temp to cooler)
-    Daemon,
+    #[command(args_conflicts_with_subcommands = true)]
+    Daemon {
+        #[command(subcommand)]
+        command: Option<DaemonCommands>,
+        #[command(flatten)]
+        args: Box<DaemonArgs>,
+    },
+    /// Dump MSI cooler feature report (for debugging)
+    Dump(DumpArgs),
+    /// Apply a canned profile to all supported devices
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommands,
+    },
+    /// Show the state of all supported devices
+    Status {
+        /// Print a JSON object instead of text
+        #[arg(long)]
+        json: bool,
+        /// Refresh every second until interrupted
+        #[arg(long)]
+        watch: bool,
+        /// Query the devices in parallel, giving up on any that take over 2s
+        #[arg(long)]
+        all: bool,
+    },
+}
+
+#[derive(Args)]
+struct DaemonArgs {
+    /// Stay attached to the terminal, or detach into the background (logging to
+    /// ~/.local/share/lights-out/daemon.log)
+    #[arg(long, value_enum, default_value_t = DaemonMode::Foreground)]
+    daemon_mode: DaemonMode,
+    /// PID file written by the daemon and read by `daemon stop` and --pid-check
+    /// [default: $XDG_RUNTIME_DIR/lights-out-daemon.pid, /run for root, else /tmp]
+    #[arg(long, global = true)]
+    pid_file: Option<std::path::PathBuf>,
+    /// Refuse to start while the daemon in --pid-file is still running, since two
+    /// daemons would fight over the cooler (false: skip the check and the PID file
+    /// in foreground mode)
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+    pid_check: bool,
+    /// Stop the running daemon found by --pid-check instead of refusing to start
+    #[arg(long)]
+    force: bool,
+    /// How often to send the temperature to the AIO (e.g. 500ms, 2s)
+    #[arg(long, visible_alias = "send-interval", default_value = "2s", value_parser = parse_duration)]
+    interval: Duration,
+    /// How often to read the temperature sensor (defaults to --interval); the
+    /// last reading is re-sent in between so the AIO keeps getting updates
+    #[arg(long, value_parser = parse_duration)]
+    measure_interval: Option<Duration>,
+    /// How often to print the temperature (defaults to every cycle)
+    #[arg(long, value_parser = parse_duration)]
+    report_interval: Option<Duration>,
+    /// Read the temperature from this file (millidegrees C) instead of searching hwmon,
+    /// e.g. /sys/class/hwmon/hwmon3/temp2_input
+    #[arg(long)]
+    secondary_sensor: Option<std::path::PathBuf>,
+    /// Read the k10temp sensor labeled Tdie instead of Tctl (temp1_input), which
+    /// some Ryzen CPUs report up to 27°C high
+    #[arg(long, conflicts_with = "secondary_sensor")]
+    cpu_tdie: bool,
+    /// Ping the systemd watchdog (WatchdogSec=) every cycle
+    #[arg(long)]
+    watchdog: bool,
+    /// Serve Prometheus metrics at http://<host>:<port>/metrics
+    #[arg(long, value_name = "PORT")]
+    metrics_server: Option<u16>,
+    /// Address --metrics-server listens on (0.0.0.0 for every interface)
+    #[arg(
+        long,
+        value_name = "ADDR",
+        default_value = "127.0.0.1",
+        requires = "metrics_server"
+    )]
+    metrics_bind: std::net::IpAddr,
+    /// Push CPU temperature, pump speed and fan mode to a time-series database
+    /// after each measurement (failed pushes are only warned about)
+    #[arg(long, value_enum, requires_all = ["url", "token", "bucket"])]
+    export_metrics: Option<MetricsExport>,
+    /// InfluxDB base URL, e.g. http://localhost:8086
+    #[arg(long, requires = "export_metrics")]
+    url: Option<String>,
+    /// InfluxDB API token (prefer $INFLUX_TOKEN, which stays out of the process list)
+    #[arg(
+        long,
+        env = "INFLUX_TOKEN",
+        hide_env_values = true,
+        requires = "export_metrics"
+    )]
+    token: Option<String>,
+    /// InfluxDB bucket to write to
+    #[arg(long, requires = "export_metrics")]
+    bucket: Option<String>,
+    /// InfluxDB organization (needed unless the token is scoped to one)
+    #[arg(long, requires = "export_metrics")]
+    org: Option<String>,
+    /// Shell command run (via sh -c, without waiting) after each measurement,
+    /// with the temperature in $TEMP
+    #[arg(long)]
+    post_command: Option<String>,
+    /// Write the CPU temperature (integer °C and a newline) to this file every cycle
+    #[arg(long)]
+    write_temp_to_file: Option<std::path::PathBuf>,
+    /// Flash all MSI LEDs in --alert-color while the CPU is at or above this temperature (°C)
+    #[arg(long)]
+    alert_on_overheat: Option<i32>,
+    /// Shell command (sh -c, temperature in $TEMP) run when the CPU reaches the
+    /// matching --alert-threshold; repeat both for tiered alerts
+    #[arg(long = "alert-command")]
+    alert_commands: Vec<String>,
+    /// Temperature (°C) for the preceding --alert-command
+    #[arg(long = "alert-threshold")]
+    alert_thresholds: Vec<i32>,
+    /// Minimum time between runs of the same --alert-command (e.g. 300 or 5m)
+    #[arg(long, default_value = "300", value_parser = parse_duration)]
+    alert_cooldown: Duration,
+    /// Color used for the overheat alert
+    #[arg(long, default_value = "255,0,0")]
+    alert_color: Rgb,
+    /// Also show a desktop notification (notify-send) when the alert starts
+    #[arg(long, requires = "alert_on_overheat")]
+    alert_notify: bool,
+    /// Also read and print amdgpu temperature, shader clock and utilization
+    #[arg(long)]
+    gpu_metrics: bool,
+    /// Added to the temperature sent to the AIO, e.g. -27 to turn Ryzen Tctl
+    /// into Tdie for Smart mode (the printed temperature is not adjusted)
+    #[arg(long, default_value_t = 0, allow_negative_numbers = true)]
+    temp_offset: i32,
+    /// Send the average of the last N readings instead of the latest one, to keep
+    /// fast-moving sensors like Ryzen Tctl from jerking the Smart mode fan curve
+    #[arg(long, value_name = "SAMPLES",
+          value_parser = clap::value_parser!(u8).range(1..=msi::MAX_TEMP_AVERAGE_SAMPLES as i64))]
+    cpu_temp_average: Option<u8>,
+    /// Print temperatures in °F instead of °C (the AIO, $TEMP, --write-temp-to-file
+    /// and metrics stay in °C)
+    #[arg(long)]
+    temp_fahrenheit: bool,
+    /// Also print min/max/avg temperature, failed reads and uptime this often (e.g. 60)
+    #[arg(long, value_parser = parse_duration)]
+    stats_interval: Option<Duration>,
+    /// Switch the fan mode between --auto-fan-low and --auto-fan-high around
+    /// this CPU temperature (°C)
+    #[arg(long, value_name = "TEMP")]
+    auto_fan: Option<i32>,
+    /// Fan mode below the --auto-fan temperature
+    #[arg(long, value_enum, default_value_t = FanMode::Silent)]
+    auto_fan_low: FanMode,
+    /// Fan mode above the --auto-fan temperature
+    #[arg(long, value_enum, default_value_t = FanMode::Game)]
+    auto_fan_high: FanMode,
+    /// Switch up only at --auto-fan plus this many degrees, and down only at
+    /// --auto-fan minus this many, so a temperature hovering around it doesn't
+    /// flip the mode back and forth
+    #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(i32).range(0..=50))]
+    fan_hysteresis: i32,
+    /// Readings in a row the switch condition must hold before the mode changes
+    #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u32).range(1..))]
+    fan_hysteresis_readings: u32,
+    /// Drive every connected MSI CORELIQUID instead of only the first (fan
+    /// readbacks for metrics still come from the first)
+    #[arg(long)]
+    multi_device: bool,
+    /// Raise the pump curve so the pump never runs below this speed (0-100%, see
+    /// `msi pump-speed set`); switches the pump to the customize mode at startup
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=100))]
+    min_pump: Option<u8>,
+    /// Fan mode to set when the daemon stops (Ctrl+C or SIGTERM)
+    #[arg(long, value_enum)]
+    fan_mode_on_exit: Option<FanMode>,
+    /// What to do with the LCD when the daemon stops
+    #[arg(long, value_enum, default_value_t = LcdAction::Keep)]
+    lcd_on_exit: LcdAction,
+    /// LED effect driven by system state (applied to the MSI LED zones)
+    #[arg(long, value_enum)]
+    effect: Option<DaemonEffect>,
+    /// Base CPU frequency in MHz (overclock-indicator shows --base-color here)
+    #[arg(long, default_value_t = 3600)]
+    base_freq: u32,
+    /// Boost CPU frequency in MHz (overclock-indicator shows --boost-color here)
+    #[arg(long, default_value_t = 5200)]
+    boost_freq: u32,
+    /// Color at base frequency
+    #[arg(long, default_value = "0,0,255")]
+    base_color: Rgb,
+    /// Color at boost frequency
+    #[arg(long, default_value = "255,0,0")]
+    boost_color: Rgb,
+    /// CPU temperature (°C) shown as --base-color by cpu-reactive
+    #[arg(long, default_value_t = 30)]
+    cold_temp: i32,
+    /// CPU temperature (°C) shown as --boost-color by cpu-reactive
+    #[arg(long, default_value_t = 90)]
+    hot_temp: i32,
+    /// Use a matplotlib colormap instead of blending --base-color and --boost-color
+    #[arg(long, value_parser = clap::builder::PossibleValuesParser::new(color::COLORMAP_NAMES))]
+    colormap: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum ProfileCommands {
+    /// Apply a built-in LED preset to the MSI cooler and LianLi fans
+    Apply {
+        #[arg(value_enum)]
+        profile: LedPreset,
+        /// Switch instantly or fade from the current colors
+        #[arg(long, value_enum, default_value_t = Transition::Instant)]
+        transition: Transition,
+        /// Fade length in milliseconds
+        #[arg(long, default_value_t = 1000)]
+        duration: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum DisableOnBootCommands {
+    /// Install a systemd generator, udev rule and modules-load entry (needs root)
+    Install,
+    /// Remove the files written by install
+    Uninstall,
+}
+
+#[derive(Args)]
+struct TempSimulateArgs {
+    /// Temperature pattern: sine, sawtooth, step <TEMP> or ramp <START> <END> <DURATION>
+    #[arg(long, required = true, num_args = 1..=4, value_name = "PATTERN")]
+    pattern: Vec<String>,
+    /// Length of one sine or sawtooth cycle (e.g. 60s, 5m)
+    #[arg(long, default_value = "60s", value_parser = parse_duration)]
+    period: Duration,
+    /// Lowest temperature of the sine and sawtooth patterns (°C)
+    #[arg(long, default_value_t = 40)]
+    min: i32,
+    /// Highest temperature of the sine and sawtooth patterns (°C)
+    #[arg(long, default_value_t = 85)]
+    max: i32,
+}
+
+#[derive(Subcommand)]
+enum DaemonCommands {
+    /// Stop a daemon started with --daemon-mode background
+    Stop,
+}
+
+/// Time-series databases `daemon --export-metrics` can push to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum MetricsExport {
+    /// InfluxDB 2.x line protocol over HTTP
+    Influxdb,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum DaemonMode {
+    Foreground,
+    Background,
+}
+
+/// LED effects available in the daemon
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum DaemonEffect {
+    /// Blend from --base-color to --boost-color as the fastest core boosts
+    OverclockIndicator,
+    /// Blend from --base-color at --cold-temp to --boost-color at --hot-temp
+    CpuReactive,
+    /// Dominant color of the desktop wallpaper (GNOME, KDE or XFCE), on all devices
+    WallpaperReactive,
+}
+
+impl DaemonArgs {
+    /// Effect color at `t` (0.0..=1.0): from --colormap, or blended base to boost color
+    fn effect_color(&self, t: f32) -> Rgb {
+        self.colormap
+            .as_deref()
+            .and_then(|name| colormap_lookup(name, t))
+            .unwrap_or_else(|| lerp_color(self.base_color, self.boost_color, t))
+    }
+
+    fn shutdown(&self) -> DaemonShutdown {
+        DaemonShutdown {
+            fan_mode: self.fan_mode_on_exit,
+            lcd_action: self.lcd_on_exit,
+        }
+    }
+}
+
+/// How raw packets are filled up to the device's packet length
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Padding {
+    /// Zero-pad to the full packet length
+    Zeros,
+    /// Send exactly the given bytes
+    None,
+}
+
+/// Profile formats `msi export-profile` can write
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    /// OpenRGB JSON profile, readable by `msi import-profile`
+    Openrgb,
+}
+
+/// Devices `msi led-sync` can take its color from
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum SyncSource {
+    /// Fan color of the first channel in the cached LianLi state
+    Lianli,
+}
+
+/// LCD handling when the daemon stops
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum LcdAction {
+    /// Leave the LCD as it is
+    Keep,
+    /// Turn the LCD off
+    Disable,
+}
+
+/// Cleanup performed when the daemon stops
+struct DaemonShutdown {
+    fan_mode: Option<FanMode>,
+    lcd_action: LcdAction,
+}
+
+impl DaemonShutdown {
+    fn run(&self, device: &HidDevice) {
+        if let Some(mode) = self.fan_mode {
+            match msi_write_fan_mode(device, mode) {
+                Ok(()) => println!("  MSI CORELIQUID: Fan mode set to {:?}", mode),
+                Err(e) => eprintln!("  Warning: Failed to set fan mode on exit: {}", e),
+            }
+        }
+        match self.lcd_action {
+            LcdAction::Keep => {}
+            LcdAction::Disable => match msi_lcd_disable(device) {
+                Ok(()) => println!("  MSI CORELIQUID: LCD disabled"),
+                Err(e) => eprintln!("  Warning: Failed to disable LCD on exit: {}", e),
+            },
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum MsiCommands {
     /// Dump MSI cooler feature report (for debugging)
-    Dump,
+    Dump(DumpArgs),
+    /// Show the cooler's HID interfaces, kernel driver and report descriptor
+    HidInfo,
+    /// List connected coolers with the index `--device-index` takes
+    Devices,
+    /// Measure the LED feature report length and compare it with the expected 185 bytes
+    ReportSizeDetect,
+    /// Check the LED feature report's report ID, length and zone modes
+    ValidateProtocol,
+    /// Fan control commands
+    Fan {
+        #[command(subcommand)]
+        command: MsiFanCommands,
+    },
+    /// Pump speed commands
+    PumpSpeed {
+        #[command(subcommand)]
+        command: MsiPumpSpeedCommands,
+    },
+    /// Set the animation mode of one LED zone
+    LedMode {
+        /// Zone index (0-16, see `LED_OFFSETS`)
+        #[arg(long)]
+        zone: usize,
+        /// Mode name (e.g. breathing) or raw mode byte
+        #[arg(long, value_parser = parse_msi_led_mode)]
+        mode: u8,
+    },
+    /// Light each LED zone in turn to map zones to physical positions
+    LedTest {
+        /// Advance automatically after this long (e.g. 1s); without it, wait for Enter
+        #[arg(long, value_parser = parse_duration)]
+        dwell: Option<Duration>,
+    },
+    /// Set every LED zone to the same mode and color
+    LedAll {
+        /// Mode name (e.g. static) or raw mode byte
+        #[arg(long, value_parser = parse_msi_led_mode)]
+        mode: u8,
+        /// Color as "r,g,b", "#rrggbb" or a name
+        #[arg(long)]
+        color: Rgb,
+    },
+    /// Set only the pump head LEDs, leaving the radiator fans as they are
+    PumpHead {
+        /// Color as "r,g,b", "#rrggbb" or a name
+        #[arg(long)]
+        color: Rgb,
+    },
+    /// Set only the radiator fan LEDs, leaving the pump head as it is
+    RadiatorFans {
+        /// Color as "r,g,b", "#rrggbb" or a name
+        #[arg(long)]
+        color: Rgb,
+    },
+    /// Spread a matplotlib colormap across the LED zones (zone 0 = start of the map)
+    LedColormap {
+        #[arg(long, value_parser = clap::builder::PossibleValuesParser::new(color::COLORMAP_NAMES))]
+        colormap: String,
+    },
+    /// Copy the color last applied to another device onto all MSI LED zones
+    LedSync {
+        #[arg(long, value_enum)]
+        source: SyncSource,
+    },
+    /// Save the LED report, fan mode and device details to a JSON backup
+    EepromBackup {
+        #[arg(long)]
+        out: std::path::PathBuf,
+    },
+    /// Restore a backup written by eeprom-backup
+    EepromRestore {
+        #[arg(long = "in")]
+        input: std::path::PathBuf,
+        /// Restore even if the backup came from a different firmware release
+        #[arg(long)]
+        force: bool,
+    },
+    /// Look up the cooler's USB release (bcdDevice) in a table of known releases
+    FirmwareUpdateCheck {
+        /// Fetch the latest table from GitHub (falls back to the built-in one)
+        #[arg(long)]
+        online: bool,
+    },
+    /// Save the current zone modes and colors as a profile for another tool
+    ExportProfile {
+        #[arg(long, value_enum)]
+        format: ExportFormat,
+        /// Output file (e.g. profile.orp)
+        #[arg(long)]
+        out: std::path::PathBuf,
+    },
+    /// Show which header/LED each zone index controls
+    LedZoneMap,
+    /// Apply the MSI zone settings from an OpenRGB profile
+    ImportProfile {
+        /// OpenRGB profile (.orp, JSON format versions 1 and 2)
+        #[arg(long)]
+        from_openrgb: std::path::PathBuf,
+    },
+    /// Apply a built-in LED preset: static red, static blue or rainbow
+    LedPreset {
+        #[arg(value_enum)]
+        profile: LedPreset,
+    },
+    /// Send a CPU temperature to the cooler to test the Smart mode fan curve
+    TestTempSend {
+        /// Temperature in degrees Celsius
+        #[arg(value_parser = clap::value_parser!(i32).range(0..=125))]
+        value: i32,
+        /// Number of times to send it
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+        count: u32,
+        /// Delay between sends in milliseconds
+        #[arg(long, default_value_t = 1000)]
+        interval: u64,
+    },
+    /// Run the daemon on a simulated CPU temperature instead of hwmon, to watch
+    /// the Smart mode fan curve (or --effect cpu-reactive) across a range
+    TempSimulate {
+        #[command(flatten)]
+        simulation: TempSimulateArgs,
+        #[command(flatten)]
+        daemon: Box<DaemonArgs>,
+    },
+    /// Send a raw HID packet to the cooler (for protocol experiments)
+    WriteRaw {
+        /// Packet bytes in hex, e.g. "d0 85 00" or "0xd0 0x85" (zero-padded)
+        #[arg(required = true, num_args = 1..)]
+        bytes: Vec<String>,
+        /// Send as a feature report instead of an output report
+        #[arg(long)]
+        feature_report: bool,
+        /// Read back a feature report afterwards and dump it
+        #[arg(long)]
+        read_after: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum MsiFanCommands {
+    /// Read back the current fan mode and print its name
+    #[command(visible_alias = "auto-detect-mode")]
+    Read,
+    /// Make Smart mode more (>1) or less (<1) aggressive (no argument: show it)
+    ///
+    /// The cooler has no setting for this, so the daemon multiplies the CPU
+    /// temperature it sends by this factor. That scales the whole curve around
+    /// 0°C rather than shifting it: 1.2 turns 40°C into 48°C but 80°C into 96°C,
+    /// so the fans ramp sooner and reach full speed well before the CPU is hot.
+    /// The cooler also shows and logs the scaled temperature, and only the
+    /// daemon applies it. Saved in the config file; restart the daemon to apply.
+    SpeedMultiplier {
+        /// Factor (0.5-2.0); 1 sends the real temperature
+        #[arg(value_parser = parse_fan_speed_multiplier)]
+        multiplier: Option<f32>,
+    },
+    /// Step through every fan mode, printing fan speeds in each, then restore
+    /// the original mode and curves
+    ModeCycle {
+        /// Time in each mode before reading the fan speeds (e.g. 5s)
+        #[arg(long, default_value = "5s", value_parser = parse_duration)]
+        dwell: Duration,
+        /// Wait for Enter before reading the speeds and moving on (q to quit)
+        #[arg(long)]
+        interactive: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum MsiPumpSpeedCommands {
+    /// Run the pump at a fixed speed, overriding the fan mode for the pump only
+    Set {
+        /// Pump speed (0 = slowest safe speed, not off; 100 = maximum)
+        #[arg(value_parser = clap::value_parser!(u8).range(0..=100))]
+        percentage: u8,
+    },
+}
+
+#[derive(Subcommand)]
+enum LianliCommands {
+    /// Record how many fans are on each channel (no arguments: show the current map)
+    PortMap {
+        /// Channel index (0-3); repeat together with --fans
+        #[arg(long = "channel", value_parser = clap::value_parser!(u8).range(0..lianli::NUM_CHANNELS as i64))]
+        channels: Vec<u8>,
+        /// Number of fans on the preceding --channel (0-4)
+        #[arg(long = "fans", value_parser = clap::value_parser!(u8).range(0..=lianli::MAX_FANS_PER_CHANNEL as i64))]
+        fans: Vec<u8>,
+    },
+    /// Loop through a sequence of effects, e.g. "static:red:5s,fade:blue:2s,rainbow:3s"
+    ///
+    /// Steps are static:<color>:<time>, fade:<color>:<time> (from the previous
+    /// color) and rainbow:<time>. Colors are names or #rrggbb.
+    EffectChain { spec: String },
+    /// Send raw packets to the hub (for protocol experiments)
+    HubPassthrough {
+        /// Packet bytes in hex, e.g. "e0:30:ff:00:00"; repeat to send several in sequence
+        #[arg(long = "packet", required = true)]
+        packets: Vec<String>,
+        /// Pad each packet with zeros to the 65-byte command length, or send as given
+        /// (e.g. for variable-length color packets)
+        #[arg(long, value_enum, default_value_t = Padding::Zeros)]
+        padding: Padding,
+    },
+    /// Show the hub's firmware version and channel/fan/LED limits
+    ControllerInfo {
+        /// Print a JSON object instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the packets the cached state would send, without touching the hub
+    HexDump {
+        #[arg(long = "type", value_enum)]
+        kind: LianliPacketType,
+        /// Channel index (0-3); all configured channels if omitted
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..lianli::NUM_CHANNELS as i64))]
+        channel: Option<u8>,
+    },
+    /// Stream per-LED frames from stdin, one line of hex RGB per frame
+    ///
+    /// Each line holds r,g,b bytes for every LED of every configured channel in
+    /// order: per channel, all fan-ring LEDs, then all edge-ring LEDs.
+    DirectMode {
+        /// Maximum frames per second (the hub needs ~80 ms per channel per frame)
+        #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u32).range(1..))]
+        fps: u32,
+    },
+    /// Light each LED in turn to work out the LED order within a fan
+    TestPattern {
+        /// Channel index (0-3); all channels if omitted
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..lianli::NUM_CHANNELS as i64))]
+        channel: Option<u8>,
+        /// Advance automatically after this long (e.g. 500ms); without it, wait for Enter
+        #[arg(long, value_parser = parse_duration)]
+        dwell: Option<Duration>,
+    },
+    /// Flash one channel white/off so its fans can be located; other channels are untouched
+    Identify {
+        /// Channel index (0-3)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..lianli::NUM_CHANNELS as i64))]
+        channel: u8,
+        /// How long to flash (e.g. 5s)
+        #[arg(long, default_value = "5s", value_parser = parse_duration)]
+        duration: Duration,
+    },
+    /// Run a software animation on all channels until Ctrl+C, then restore the
+    /// cached state
+    ColorAnimation {
+        #[arg(long = "type", value_enum)]
+        kind: LianliAnimation,
+        /// Animation speed (1 = slowest, 5 = fastest) [default: each channel's
+        /// configured speed, else 3]
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=5))]
+        speed: Option<u8>,
+        /// Color as "r,g,b", "#rrggbb" or a name
+        #[arg(long, default_value = "white")]
+        color: Rgb,
+    },
+    /// Set the fan blade and edge ring LEDs of all channels to separate colors
+    ColorZones {
+        /// Fan blade (inner ring) color as "r,g,b", "#rrggbb" or a name
+        #[arg(long)]
+        fan: Rgb,
+        /// Edge (outer ring) color
+        #[arg(long)]
+        edge: Rgb,
+    },
+    /// Apply a preset multi-color gradient across all fans
+    ColorScene {
+        #[arg(long, value_enum)]
+        scene: ColorScene,
+    },
+    /// Color each channel with the dominant color of one strip of an image
+    ///
+    /// The image is cut into --zones vertical strips, left to right; strip N
+    /// colors the Nth configured channel.
+    ColorFromImage {
+        /// Image file (JPEG, PNG or WebP), e.g. your wallpaper
+        #[arg(long)]
+        path: std::path::PathBuf,
+        /// Number of strips [default: one per configured channel]
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=lianli::NUM_CHANNELS as i64))]
+        zones: Option<u8>,
+    },
+    /// Print fan RPMs on every channel for a while as a fan health check
+    ///
+    /// The AL V2 protocol used here has no known RPM readback, so this
+    /// currently always fails with a protocol error.
+    FanTest {
+        /// How long to measure (e.g. 5s)
+        #[arg(long, default_value = "5s", value_parser = parse_duration)]
+        duration: Duration,
+    },
+    /// Set one channel to a hue on the HSV color wheel (0 = red, 120 = green, 240 = blue)
+    ColorWheel {
+        /// Channel index (0-3)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..lianli::NUM_CHANNELS as i64))]
+        channel: u8,
+        /// Hue in degrees (0-359)
+        #[arg(long, value_parser = clap::value_parser!(u16).range(0..360))]
+        position: u16,
+        /// Saturation (0-100%)
+        #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u8).range(0..=100))]
+        saturation: u8,
+        /// Value/brightness (0-100%)
+        #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u8).range(0..=100))]
+        value: u8,
+    },
+    /// Run a different effect on each channel
+    ///
+    /// e.g. --channel 0 --effect breathing --color red --channel 1 --effect static --color blue
+    PerChannelEffect {
+        /// Channel index (0-3); repeat together with --effect and --color
+        #[arg(long = "channel", required = true, value_parser = clap::value_parser!(u8).range(0..lianli::NUM_CHANNELS as i64))]
+        channels: Vec<u8>,
+        /// Effect for the preceding --channel
+        #[arg(long = "effect", value_enum)]
+        effects: Vec<LianliEffect>,
+        /// Color for the preceding --channel (name or #rrggbb)
+        #[arg(long = "color")]
+        colors: Vec<Rgb>,
+    },
+    /// Set all fans to white at a color temperature, e.g. 3200 (warm) or 6500 (daylight)
+    ColorTemp {
+        /// Color temperature in kelvin (1000-40000)
+        #[arg(value_parser = clap::value_parser!(u32).range(1000..=40000))]
+        kelvin: u32,
+        /// Scale the resulting color (0-100%)
+        #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u8).range(0..=100))]
+        brightness: u8,
+    },
+    /// Write the last-applied hub settings to the state file (or --output)
+    SaveState {
+        /// Where to write [default: ~/.local/share/lights-out/lianli-state.json]
+        #[arg(long)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Re-apply settings from the state file (or --input) to the hub
+    LoadState {
+        /// State file to apply [default: ~/.local/share/lights-out/lianli-state.json]
+        #[arg(long)]
+        input: Option<std::path::PathBuf>,
+    },
+    /// Change the animation speed, keeping the current mode and brightness
+    Speed {
+        /// Channel index (0-3); all channels if omitted
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..lianli::NUM_CHANNELS as i64))]
+        channel: Option<u8>,
+        /// Animation speed
+        #[arg(long, value_enum)]
+        speed: LianliSpeed,
+    },
+}
+
+#[derive(Subcommand)]
+enum GpuCommands {
+    /// Read the ENE controller version string and show its register layout
+    AutoDetectEne,
+    /// Check the kernel modules, i2c device node and group membership needed to
+    /// reach the GPU without root, with a fix for each failed check
+    CheckPermissions,
+    /// Time repeated reads of the ENE device name register to check i2c bus stability
+    I2cHealthCheck {
+        /// Number of transactions
+        #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u32).range(1..))]
+        count: u32,
+    },
+    /// Turn the LEDs back on, restoring the state saved by the last disable
+    Enable {
+        /// Set this mode instead of restoring the saved state
+        #[arg(long, value_enum)]
+        mode: Option<GpuLedMode>,
+        /// Effect color as "r,g,b", "#rrggbb" or a name (implies --mode static if no mode given)
+        #[arg(long)]
+        rgb: Option<Rgb>,
+    },
+    /// Select an ENE effect mode (mode values may differ between ENE versions)
+    SetMode {
+        #[arg(long, value_enum)]
+        mode: GpuLedMode,
+        /// Color as "r,g,b", "#rrggbb" or a name (static and breathing only)
+        #[arg(long)]
+        rgb: Option<Rgb>,
+    },
+    /// Turn off ASUS Aura LEDs through the sysfs LED class (asus-wmi) instead of i2c
+    FallbackViaSysfs {
+        /// LED directory or its brightness file [default: /sys/class/leds/asus::aura*]
+        #[arg(long)]
+        path: Option<std::path::PathBuf>,
+    },
+    /// Capture i2c transactions on the GPU bus with the kernel tracer (needs root)
+    MonitorI2cTraffic {
+        /// i2c device to watch [default: the AMDGPU OEM bus]
+        #[arg(long)]
+        bus: Option<String>,
+        /// How long to capture (Ctrl+C stops early)
+        #[arg(long, default_value = "10s", value_parser = parse_duration)]
+        duration: Duration,
+    },
+    /// Scan the GPU i2c bus for responding addresses, like i2cdetect
+    EnumerateSlaves {
+        /// i2c device to scan [default: the AMDGPU OEM bus]
+        #[arg(long)]
+        bus: Option<String>,
+        /// Don't ask for confirmation before probing
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Read and display all ENE registers from 0x8000 to 0x80FF
+    EneDump {
+        /// Re-read once a second and redraw in place
+        #[arg(long)]
+        watch: bool,
+    },
+    /// Print the current LED color (from the saved state if it can't be read back)
+    ReadColor,
+    /// Replay the ENE LED-off write sequence one SMBus transaction at a time,
+    /// reading the mode back at the end (use --verbose to log every step)
+    TestWriteSequence,
+    /// Turn the GPU LEDs off early in boot, as soon as the i2c bus appears
+    DisableOnBoot {
+        #[command(subcommand)]
+        command: DisableOnBootCommands,
+    },
+}
+
+#[derive(Args)]
+struct DumpArgs {
+    /// Try every report ID from 0x00 to 0xFF and dump the ones that answer
+    #[arg(long, conflicts_with = "report_id")]
+    all_reports: bool,
+    /// Read a single report ID instead of the LED report (e.g. 0x60)
+    #[arg(long, value_parser = parse_u8)]
+    report_id: Option<u8>,
+    /// Print the LED report one byte per line with field names
+    #[arg(long, conflicts_with_all = ["all_reports", "report_id"])]
+    annotate: bool,
+    /// Save the current LED report as the baseline for --only-changed
+    #[arg(long, conflicts_with_all = ["all_reports", "report_id"])]
+    save_baseline: bool,
+    /// Only show LED report bytes that differ from the baseline
+    #[arg(long, conflicts_with_all = ["all_reports", "report_id", "save_baseline"])]
+    only_changed: bool,
+    /// Baseline file (raw report bytes) for --save-baseline/--only-changed
+    /// [default: ~/.local/share/lights-out/msi-baseline.bin]
+    #[arg(long)]
+    baseline: Option<std::path::PathBuf>,
+    /// Take this many timestamped LED report snapshots and write them to --output
+    #[arg(long, value_name = "N-SAMPLES", requires = "output",
+          conflicts_with_all = ["all_reports", "report_id", "save_baseline", "only_changed"])]
+    record: Option<u32>,
+    /// Time between --record snapshots
+    #[arg(long, default_value = "100ms", value_parser = parse_duration)]
+    interval: Duration,
+    /// Recording file written by --record
+    #[arg(long, requires = "record")]
+    output: Option<std::path::PathBuf>,
+    /// Show the snapshots in a --record file one per second (no device needed)
+    #[arg(long, conflicts_with_all = ["all_reports", "report_id", "save_baseline",
+                                      "only_changed", "record"])]
+    replay: Option<std::path::PathBuf>,
+}
+
+impl DumpArgs {
+    fn baseline_path(&self) -> std::path::PathBuf {
+        self.baseline
+            .clone()
+            .unwrap_or_else(|| data_dir().join("msi-baseline.bin"))
+    }
+}
+
+/// Parse a duration like "500ms", "2s", "5m" or "1h" (bare numbers are seconds)
+fn parse_duration(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (num, unit) = s.split_at(split);
+    let num: f64 = num
+        .parse()
+        .with_context(|| format!("Invalid duration: {}", s))?;
+    let secs = match unit.trim() {
+        "ms" => num / 1000.0,
+        "" | "s" => num,
+        "m" => num * 60.0,
+        "h" => num * 3600.0,
+        _ => anyhow::bail!("Invalid duration unit in {:?} (expected ms, s, m or h)", s),
+    };
+    Duration::try_from_secs_f64(secs).with_context(|| format!("Duration out of range: {}", s))
+}
+
+/// Set up a Ctrl+C/SIGTERM handler for graceful shutdown of long-running commands
+fn install_stop_handler() -> Result<Arc<AtomicBool>> {
+    let stop_flag = Arc::new(AtomicBool::new(false));
+    let stop_flag_clone = stop_flag.clone();
+
+    ctrlc::set_handler(move || {
+        println!("\n  Received shutdown signal...");
+        stop_flag_clone.store(true, Ordering::Relaxed);
+    })
+    .context("Failed to set signal handler")?;
+
+    Ok(stop_flag)
+}
+
+/// Sleep for `duration`, returning early if the stop flag is set
+fn sleep_unless_stopped(stop_flag: &AtomicBool, duration: Duration) {
+    let deadline = Instant::now() + duration;
+    while !stop_flag.load(Ordering::Relaxed) {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        std::thread::sleep(remaining.min(Duration::from_millis(msi::DAEMON_POLL_MS)));
+    }
+}
+
+/// Parse a byte value given as decimal or 0x-prefixed hex
+fn parse_u8(s: &str) -> Result<u8> {
+    let s = s.trim();
+    let val = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u8::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    val.with_context(|| format!("Invalid byte value: {}", s))
+}
+
+/// Check whether an interface is a mouse/keyboard emulation endpoint
+fn is_input_emulation(info: &DeviceInfo) -> bool {
+    info.usage_page() == hid::USAGE_PAGE_GENERIC_DESKTOP
+        && (info.usage() == hid::USAGE_MOUSE || info.usage() == hid::USAGE_KEYBOARD)
+}
+
+/// Open the vendor-defined HID interface of a device
+///
+/// Some devices expose several interfaces under the same VID/PID, and
+/// `HidApi::open` just takes the first one. Prefer the interface on a
+/// vendor-defined usage page, falling back to any interface that isn't
+/// mouse/keyboard emulation (some backends don't report usage pages).
+fn open_hid_device(api: &HidApi, vid: u16, pid: u16, name: &str) -> Result<HidDevice> {
+    let device_info = select_hid_interface(&hid_interfaces(api, vid, pid))
+        .ok_or_else(|| LightsOutError::DeviceNotFound(anyhow!("{} not found", name)))?;
+
+    api.open_path(device_info.path())
+        .with_context(|| format!("Failed to open {}", name))
+}
+
+/// All HID interfaces exposed under a VID/PID
+fn hid_interfaces(api: &HidApi, vid: u16, pid: u16) -> Vec<&DeviceInfo> {
+    api.device_list()
+        .filter(|d| d.vendor_id() == vid && d.product_id() == pid)
+        .collect()
+}
+
+/// The interface `open_hid_device` picks out of `hid_interfaces`
+fn select_hid_interface<'a>(candidates: &[&'a DeviceInfo]) -> Option<&'a DeviceInfo> {
+    candidates
+        .iter()
+        .find(|d| d.usage_page() >= hid::USAGE_PAGE_VENDOR_DEFINED)
+        .or_else(|| candidates.iter().find(|d| !is_input_emulation(d)))
+        .copied()
+}
+
+/// sysfs directory of a hidraw node ("/dev/hidraw3" -> /sys/class/hidraw/hidraw3/device)
+fn hidraw_sysfs_dir(info: &DeviceInfo) -> Option<std::path::PathBuf> {
+    let path = info.path().to_str().ok()?;
+    let node = Path::new(path).file_name()?;
+    Some(Path::new("/sys/class/hidraw").join(node).join("device"))
+}
+
+fn msi_hid_info() -> Result<()> {
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let candidates = hid_interfaces(&api, msi::VID, msi::PID);
+    if candidates.is_empty() {
+        return Err(LightsOutError::DeviceNotFound(anyhow!("MSI CORELIQUID not found")).into());
+    }
+    let selected = msi_selected_interface(&api).ok().map(|d| d.path());
+
+    for info in candidates {
+        let marker = if Some(info.path()) == selected {
+            " (used by lights-out)"
+        } else {
+            ""
+        };
+        println!("{}{}", info.path().to_string_lossy(), marker);
+        println!(
+            "  VID:PID:          {:04x}:{:04x}",
+            info.vendor_id(),
+            info.product_id()
+        );
+        println!(
+            "  Manufacturer:     {}",
+            info.manufacturer_string().unwrap_or("-")
+        );
+        println!(
+            "  Product:          {}",
+            info.product_string().unwrap_or("-")
+        );
+        println!(
+            "  Serial number:    {}",
+            info.serial_number().unwrap_or("-")
+        );
+        println!("  Usage page:       0x{:04x}", info.usage_page());
+        println!("  Usage:            0x{:04x}", info.usage());
+        println!("  Interface number: {}", info.interface_number());
+
+        let sysfs = hidraw_sysfs_dir(info);
+        let driver = sysfs
+            .as_ref()
+            .and_then(|dir| fs::read_link(dir.join("driver")).ok())
+            .and_then(|link| link.file_name().map(|n| n.to_string_lossy().into_owned()));
+        println!("  Kernel driver:    {}", driver.as_deref().unwrap_or("-"));
+
+        if let Some(descriptor) = sysfs.and_then(|dir| fs::read(dir.join("report_descriptor")).ok())
+        {
+            println!("  Report descriptor ({} bytes):", descriptor.len());
+            print_hex_dump(&descriptor);
+        }
+        println!();
+    }
+    Ok(())
+}
+
+/// Cooler picked with `msi --device-index`, set once before the command runs
+static MSI_DEVICE_INDEX: OnceLock<usize> = OnceLock::new();
+
+/// One interface per connected cooler, ordered by HID path
+///
+/// Uses the same preference as `select_hid_interface`: vendor-defined usage
+/// pages, else anything that isn't mouse/keyboard emulation.
+fn msi_units(api: &HidApi) -> Vec<&DeviceInfo> {
+    let candidates = hid_interfaces(api, msi::VID, msi::PID);
+    let mut units: Vec<&DeviceInfo> = candidates
+        .iter()
+        .copied()
+        .filter(|d| d.usage_page() >= hid::USAGE_PAGE_VENDOR_DEFINED)
+        .collect();
+    if units.is_empty() {
+        units = candidates
+            .into_iter()
+            .filter(|d| !is_input_emulation(d))
+            .collect();
+    }
+    units.sort_by(|a, b| a.path().cmp(b.path()));
+    units
+}
+
+/// Interface of the cooler `msi_open` uses
+fn msi_selected_interface(api: &HidApi) -> Result<&DeviceInfo> {
+    let index = MSI_DEVICE_INDEX.get().copied().unwrap_or(0);
+    let units = msi_units(api);
+    match units.get(index) {
+        Some(info) => Ok(info),
+        None if units.is_empty() => {
+            Err(LightsOutError::DeviceNotFound(anyhow!("MSI CORELIQUID not found")).into())
+        }
+        None => Err(LightsOutError::DeviceNotFound(anyhow!(
+            "MSI CORELIQUID #{} not found ({} connected)",
+            index,
+            units.len()
+        ))
+        .into()),
+    }
+}
+
+fn msi_open(api: &HidApi) -> Result<HidDevice> {
+    let info = msi_selected_interface(api)?;
+    api.open_path(info.path())
+        .context("Failed to open MSI CORELIQUID")
+}
+
+/// Open every connected cooler, for `daemon --multi-device`
+fn msi_open_all(api: &HidApi) -> Result<Vec<HidDevice>> {
+    let units = msi_units(api);
+    if units.is_empty() {
+        return Err(LightsOutError::DeviceNotFound(anyhow!("MSI CORELIQUID not found")).into());
+    }
+    units
+        .into_iter()
+        .map(|info| {
+            api.open_path(info.path()).with_context(|| {
+                format!(
+                    "Failed to open MSI CORELIQUID at {}",
+                    info.path().to_string_lossy()
+                )
+            })
+        })
+        .collect()
+}
+
+fn msi_devices() -> Result<()> {
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let units = msi_units(&api);
+    if units.is_empty() {
+        return Err(LightsOutError::DeviceNotFound(anyhow!("MSI CORELIQUID not found")).into());
+    }
+    for (index, info) in units.iter().enumerate() {
+        println!(
+            "  {}: {} ({}, serial {})",
+            index,
+            info.path().to_string_lossy(),
+            info.product_string().unwrap_or("MSI CORELIQUID"),
+            info.serial_number().unwrap_or("-")
+        );
+    }
+    Ok(())
+}
+
+/// Parse hex bytes separated by spaces, commas or colons ("d0 85", "0xd0,0x85", "e0:30")
+///
+/// Tokens longer than one byte without separators ("d085") are split into pairs.
+fn parse_hex_bytes(tokens: &[String]) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    for token in tokens
+        .iter()
+        .flat_map(|t| t.split(|c: char| c.is_whitespace() || c == ',' || c == ':'))
+        .filter(|t| !t.is_empty())
+    {
+        let hex = token
+            .strip_prefix("0x")
+            .or_else(|| token.strip_prefix("0X"))
+            .unwrap_or(token);
+        if hex.len() > 2 && hex.len() % 2 != 0 {
+            anyhow::bail!("Invalid hex byte sequence: {}", token);
+        }
+        for i in (0..hex.len()).step_by(2) {
+            let pair = hex.get(i..(i + 2).min(hex.len())).unwrap_or(hex);
+            bytes.push(
+                u8::from_str_radix(pair, 16)
+                    .with_context(|| format!("Invalid hex byte: {}", token))?,
+            );
+        }
+    }
+    Ok(bytes)
+}
+
+fn msi_write_raw(bytes: &[String], feature_report: bool, read_after: bool) -> Result<()> {
+    let data = parse_hex_bytes(bytes).map_err(LightsOutError::Config)?;
+    let len = if feature_report {
+        msi::MAX_DATA_LEN
+    } else {
+        msi::HID_REPORT_LEN
+    };
+    if data.is_empty() || data.len() > len {
+        return Err(LightsOutError::Config(anyhow!(
+            "Packet must be 1-{} bytes, got {}",
+            len,
+            data.len()
+        ))
+        .into());
+    }
+
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = msi_open(&api)?;
+
+    let mut buf = vec![0u8; len];
+    buf[..data.len()].copy_from_slice(&data);
+
+    if feature_report {
+        device
+            .send_feature_report(&buf)
+            .context("Failed to send feature report")?;
+    } else {
+        device.write(&buf).context("Failed to write packet")?;
+    }
+    println!("Sent {} bytes:", buf.len());
+    print_hex_dump(&buf);
+
+    if read_after {
+        // Feature reports are read back by their own ID, output reports via the LED report
+        let id = if feature_report {
+            buf[0]
+        } else {
+            msi::FEATURE_REPORT_ID
+        };
+        let mut resp = [0u8; msi::MAX_REPORT_PROBE_LEN];
+        resp[0] = id;
+        let n = device
+            .get_feature_report(&mut resp)
+            .with_context(|| format!("Failed to get feature report 0x{:02X}", id))?;
+        println!("\nFeature report 0x{:02X} ({} bytes):", id, n);
+        print_hex_dump(&resp[..n]);
+    }
+
+    Ok(())
+}
+
+fn msi_read_led_report(device: &HidDevice) -> Result<[u8; msi::MAX_DATA_LEN]> {
+    let mut buf = [0u8; msi::MAX_DATA_LEN];
+    buf[0] = msi::FEATURE_REPORT_ID;
+    let len = device
+        .get_feature_report(&mut buf)
+        .context("Failed to get feature report")?;
+    // A shorter report has a different zone layout; writing our 185-byte
+    // layout back would corrupt it (`msi_disable` copes by only touching the
+    // bytes the device sent)
+    if len < msi::MAX_DATA_LEN {
+        return Err(LightsOutError::Protocol(anyhow!(
+            "LED feature report is {} bytes, expected {} (see `msi report-size-detect`)",
+            len,
+            msi::MAX_DATA_LEN
+        ))
+        .into());
+    }
+    Ok(buf)
+}
+
+/// LED feature report cut to the length the device returned
+///
+/// Reads into an oversized buffer; if the backend doesn't report the
+/// length, falls back to the position of the last non-zero byte.
+fn msi_read_led_report_sized(device: &HidDevice) -> Result<Vec<u8>> {
+    let mut buf = vec![0u8; msi::MAX_REPORT_PROBE_LEN];
+    buf[0] = msi::FEATURE_REPORT_ID;
+    let len = device
+        .get_feature_report(&mut buf)
+        .context("Failed to get feature report")?;
+    let size = if len > 0 && len < buf.len() {
+        len
+    } else {
+        buf.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1)
+    };
+    buf.truncate(size);
+    Ok(buf)
+}
+
+/// Length of the LED feature report as returned by the device
+fn msi_detect_report_size(device: &HidDevice) -> Result<usize> {
+    Ok(msi_read_led_report_sized(device)?.len())
+}
+
+fn msi_report_size_detect() -> Result<()> {
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = msi_open(&api)?;
+
+    let size = msi_detect_report_size(&device)?;
+    println!(
+        "  MSI CORELIQUID: Feature report 0x{:02X} is {} bytes",
+        msi::FEATURE_REPORT_ID,
+        size
+    );
+    if size != msi::MAX_DATA_LEN {
+        eprintln!(
+            "  Warning: lights-out only knows the {}-byte MPG CORELIQUID layout; LED \
+             commands are not safe on this device",
+            msi::MAX_DATA_LEN
+        );
+    }
+    Ok(())
+}
+
+/// Something unexpected in an LED feature report; firmware versions differ, so
+/// these are reported rather than treated as errors
+#[derive(Debug)]
+struct ValidationWarning {
+    offset: Option<usize>,
+    message: String,
+}
+
+impl fmt::Display for ValidationWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.offset {
+            Some(offset) => write!(f, "byte {}: {}", offset, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Check an LED feature report against the layout lights-out writes
+fn msi_validate_report(buf: &[u8]) -> Result<Vec<ValidationWarning>> {
+    if buf.is_empty() {
+        return Err(LightsOutError::Protocol(anyhow!("LED feature report is empty")).into());
+    }
+
+    let mut warnings = Vec::new();
+    if buf[0] != msi::FEATURE_REPORT_ID {
+        warnings.push(ValidationWarning {
+            offset: Some(0),
+            message: format!(
+                "report ID 0x{:02X}, expected 0x{:02X}",
+                buf[0],
+                msi::FEATURE_REPORT_ID
+            ),
+        });
+    }
+    if buf.len() != msi::MAX_DATA_LEN {
+        warnings.push(ValidationWarning {
+            offset: None,
+            message: format!(
+                "report is {} bytes, expected {}",
+                buf.len(),
+                msi::MAX_DATA_LEN
+            ),
+        });
+    }
+    for (zone, &offset) in msi::LED_OFFSETS.iter().enumerate() {
+        match buf.get(offset) {
+            Some(&mode) if MsiLedMode::from_byte(mode).is_none() => {
+                warnings.push(ValidationWarning {
+                    offset: Some(offset),
+                    message: format!("zone {} has unknown mode {}", zone, mode),
+                })
+            }
+            Some(_) => {}
+            None => warnings.push(ValidationWarning {
+                offset: Some(offset),
+                message: format!("zone {} is past the end of the report", zone),
+            }),
+        }
+    }
+    Ok(warnings)
+}
+
+fn msi_validate_protocol() -> Result<()> {
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = msi_open(&api)?;
+
+    let mut buf = [0u8; msi::MAX_REPORT_PROBE_LEN];
+    buf[0] = msi::FEATURE_REPORT_ID;
+    let len = device
+        .get_feature_report(&mut buf)
+        .context("Failed to get feature report")?;
+    let warnings = msi_validate_report(&buf[..len])?;
+
+    for warning in &warnings {
+        println!("  Warning: {}", warning);
+    }
+    if warnings.is_empty() {
+        println!("  MSI CORELIQUID: LED feature report matches the expected layout");
+    } else {
+        println!(
+            "  MSI CORELIQUID: {} unexpected value(s) in the LED feature report",
+            warnings.len()
+        );
+    }
+    Ok(())
+}
+
+fn msi_write_led_report(device: &HidDevice, buf: &[u8; msi::MAX_DATA_LEN]) -> Result<()> {
+    device
+        .send_feature_report(buf)
+        .context("Failed to send feature report")
+}
+
+fn msi_disable(verbose: bool) -> Result<()> {
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = msi_open(&api)?;
+
+    // Disable LEDs via feature report. Unlike the other LED commands this
+    // works on any report size, writing back no more than the device sent.
+    let mut buf = msi_read_led_report_sized(&device)?;
+    if buf.len() != msi::MAX_DATA_LEN {
+        eprintln!(
+            "  Warning: LED feature report is {} bytes, expected {}; only disabling \
+             the zones it covers",
+            buf.len(),
+            msi::MAX_DATA_LEN
+        );
+        buf.truncate(msi::MAX_DATA_LEN);
+    }
+    if verbose {
+        for warning in msi_validate_report(&buf)? {
+            eprintln!("  Warning: LED feature report {}", warning);
+        }
+    }
+    for &offset in msi::LED_OFFSETS {
+        if offset < buf.len() {
+            buf[offset] = msi::LED_MODE_DISABLE;
+        }
+    }
+    device
+        .send_feature_report(&buf)
+        .context("Failed to send feature report")?;
+    println!("  MSI CORELIQUID: LEDs disabled");
+
+    msi_lcd_disable(&device)?;
+    println!("  MSI CORELIQUID: LCD disabled");
+
+    Ok(())
+}
+
+/// Apply a built-in LED preset to the zone fields, leaving all other report bytes as they are
+fn msi_apply_led_preset(profile: LedPreset) -> Result<()> {
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = msi_open(&api)?;
+
+    msi_write_led_preset(&device, profile)?;
+
+    println!("  MSI CORELIQUID: Applied {:?} preset", profile);
+    Ok(())
+}
+
+fn msi_write_led_preset(device: &HidDevice, profile: LedPreset) -> Result<()> {
+    let canned = profile.report();
+    let mut buf = msi_read_led_report(device)?;
+    for &offset in msi::LED_OFFSETS {
+        let zone = offset..=offset + msi::ZONE_COLOR_FLAGS;
+        buf[zone.clone()].copy_from_slice(&canned[zone]);
+    }
+    msi_write_led_report(device, &buf)
+}
+
+/// Apply a built-in LED preset to the MSI cooler and LianLi fans
+///
+/// A fade blends every zone from its current color (black if it is off) to the
+/// profile color as static frames, then writes the profile itself. The MSI
+/// cooler is read back; LianLi colors come from the state cache. LianLi frames
+/// take 4 packets per channel, so its fade runs at a lower rate than the MSI's.
+fn profile_apply(profile: LedPreset, transition: Transition, duration: Duration) -> Result<()> {
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let config = Config::load()?;
+    let mut state = LianliState::load()?;
+
+    println!("Applying {:?} profile...", profile);
+    let msi_device = msi_open(&api)
+        .map_err(|e| println!("  MSI CORELIQUID: not found or error: {}", e))
+        .ok();
+    let lianli_device = lianli_open(&api)
+        .map_err(|e| println!("  LianLi UNI FAN: not found or error: {}", e))
+        .ok();
+
+    if transition == Transition::Fade {
+        let stop_flag = install_stop_handler()?;
+        let target = profile.color();
+        let msi_from = match &msi_device {
+            Some(device) => Some(msi_read_led_report(device)?),
+            None => None,
+        };
+        let lianli_from: Vec<(u8, u8, Rgb, Rgb)> = lianli_channels(&config)
+            .into_iter()
+            .map(|(channel, fans)| {
+                let ch = state.channel(channel);
+                if ch.brightness == lianli::BRIGHTNESS_OFF {
+                    (channel, fans, Rgb::BLACK, Rgb::BLACK)
+                } else {
+                    (channel, fans, ch.fan_color, ch.edge_color)
+                }
+            })
+            .collect();
+
+        let frame = Duration::from_millis(msi::TRANSITION_FRAME_MS);
+        let start = Instant::now();
+        while !stop_flag.load(Ordering::Relaxed) && start.elapsed() < duration {
+            let frame_start = Instant::now();
+            let t = start.elapsed().as_secs_f32() / duration.as_secs_f32();
+            if let (Some(device), Some(from)) = (&msi_device, &msi_from) {
+                let mut buf = *from;
+                for &offset in msi::LED_OFFSETS {
+                    let color = offset + msi::ZONE_COLOR;
+                    let zone_from = if from[offset] == msi::LED_MODE_DISABLE {
+                        Rgb::BLACK
+                    } else {
+                        Rgb::new(from[color], from[color + 1], from[color + 2])
+                    };
+                    let rgb = lerp_color(zone_from, target, t);
+                    msi_set_zone(&mut buf, offset, msi::LED_MODE_STATIC, rgb);
+                }
+                msi_write_led_report(device, &buf)?;
+            }
+            if let Some(device) = &lianli_device {
+                for &(channel, fans, fan_from, edge_from) in &lianli_from {
+                    let fan_color = lerp_color(fan_from, target, t);
+                    let edge_color = lerp_color(edge_from, target, t);
+                    lianli_set_channel_zones(
+                        device, &mut state, channel, fans, fan_color, edge_color,
+                    )?;
+                }
+            }
+            std::thread::sleep(frame.saturating_sub(frame_start.elapsed()));
+        }
+    }
+
+    if let Some(device) = &msi_device {
+        msi_write_led_preset(device, profile)?;
+        println!("  MSI CORELIQUID: Applied {:?} preset", profile);
+    }
+    if let Some(device) = &lianli_device {
+        match profile {
+            LedPreset::Default => {
+                for (channel, _) in lianli_channels(&config) {
+                    let ch_state = LianliChannelState {
+                        mode: lianli::MODE_RAINBOW,
+                        brightness: lianli::BRIGHTNESS_FULL,
+                        ..lianli_channel_state(&state, &config, channel)
+                    };
+                    lianli_commit(device, channel, &ch_state)?;
+                    state.channels.insert(channel, ch_state);
+                }
+            }
+            LedPreset::Gaming | LedPreset::Silent => {
+                lianli_set_color(device, &config, &mut state, profile.color())?;
+            }
+        }
+        state.save()?;
+        println!("  LianLi UNI FAN: Applied {:?} preset", profile);
+    }
+    Ok(())
+}
+
+fn msi_set_led_mode(zone: usize, mode: u8) -> Result<()> {
+    let offset = *msi::LED_OFFSETS.get(zone).ok_or_else(|| {
+        LightsOutError::Config(anyhow!(
+            "Invalid zone {} (expected 0-{})",
+            zone,
+            msi::LED_OFFSETS.len() - 1
+        ))
+    })?;
+
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = msi_open(&api)?;
+
+    let mut buf = msi_read_led_report(&device)?;
+    buf[offset] = mode;
+    msi_write_led_report(&device, &buf)?;
+
+    println!("  MSI CORELIQUID: Zone {} set to mode {}", zone, mode);
+    Ok(())
+}
+
+/// Show a test step and wait for the next one
+///
+/// With `dwell`, sleeps that long (interrupted by `stop_flag`); otherwise waits
+/// for Enter. Returns false if the user asked to quit.
+fn wait_for_next_step(
+    dwell: Option<Duration>,
+    stop_flag: &AtomicBool,
+    message: &str,
+) -> Result<bool> {
+    match dwell {
+        Some(dwell) => {
+            println!("  {}", message);
+            sleep_unless_stopped(stop_flag, dwell);
+            Ok(!stop_flag.load(Ordering::Relaxed))
+        }
+        None => {
+            print!("  {} — press Enter to continue (q to quit) ", message);
+            io::Write::flush(&mut io::stdout())?;
+            let mut line = String::new();
+            Ok(io::stdin().read_line(&mut line)? != 0 && line.trim() != "q")
+        }
+    }
+}
+
+/// Light one zone at a time in white, restoring the original report afterwards
+fn msi_led_test(dwell: Option<Duration>) -> Result<()> {
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = msi_open(&api)?;
+    let original = msi_read_led_report(&device)?;
+
+    // Ctrl+C can only be caught between zones in automatic mode; in
+    // interactive mode the prompt accepts "q" instead.
+    let stop_flag = match dwell {
+        Some(_) => install_stop_handler()?,
+        None => Arc::new(AtomicBool::new(false)),
+    };
+
+    let result = (|| -> Result<()> {
+        for zone in 0..msi::LED_OFFSETS.len() {
+            if stop_flag.load(Ordering::Relaxed) {
+                break;
+            }
+            let mut buf = original;
+            for (i, &offset) in msi::LED_OFFSETS.iter().enumerate() {
+                if i == zone {
+                    msi_set_zone(&mut buf, offset, msi::LED_MODE_STATIC, Rgb::WHITE);
+                } else {
+                    msi_set_zone(&mut buf, offset, msi::LED_MODE_DISABLE, Rgb::BLACK);
+                }
+            }
+            msi_write_led_report(&device, &buf)?;
+
+            let message = format!(
+                "Zone {} (offset {}) is now lit",
+                zone,
+                msi::LED_OFFSETS[zone]
+            );
+            if !wait_for_next_step(dwell, &stop_flag, &message)? {
+                break;
+            }
+        }
+        Ok(())
+    })();
+
+    msi_write_led_report(&device, &original)?;
+    println!("  MSI CORELIQUID: Original LED settings restored");
+    result
+}
+
+fn msi_led_sync(source: SyncSource) -> Result<()> {
+    let rgb = match source {
+        SyncSource::Lianli => {
+            let state = LianliState::load()?;
+            let (channel, ch_state) = state.channels.iter().next().ok_or_else(|| {
+                LightsOutError::Config(anyhow!(
+                    "No LianLi state saved yet at {}",
+                    LianliState::path().display()
+                ))
+            })?;
+            println!(
+                "  Using LianLi channel {} fan color {}",
+                channel, ch_state.fan_color
+            );
+            ch_state.fan_color
+        }
+    };
+
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = msi_open(&api)?;
+
+    msi_set_all_leds(&device, msi::LED_MODE_STATIC, rgb)?;
+
+    println!("  MSI CORELIQUID: All zones set to {}", rgb);
+    Ok(())
+}
+
+/// LED_OFFSETS indices for an OpenRGB zone name ("JRGB1", "Onboard LEDs", "Onboard LED 3")
+fn msi_zones_for_openrgb_name(name: &str) -> Vec<usize> {
+    let normalize = |s: &str| -> String {
+        s.chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase()
+    };
+    let key = normalize(name);
+
+    // OpenRGB groups the onboard LEDs into one zone
+    if key == "onboardleds" {
+        return msi::ZONE_MAP
+            .iter()
+            .enumerate()
+            .filter(|(_, (zone_name, _))| zone_name.starts_with("Onboard LED"))
+            .map(|(zone, _)| zone)
+            .collect();
+    }
+    msi::ZONE_MAP
+        .iter()
+        .position(|(zone_name, _)| normalize(zone_name) == key)
+        .into_iter()
+        .collect()
+}
+
+/// MSI mode byte for an OpenRGB mode name ("Static", "Double Flashing", "Direct", "Off")
+fn msi_mode_for_openrgb_name(name: &str) -> Option<u8> {
+    let key = name.trim().to_ascii_lowercase().replace([' ', '_'], "-");
+    match key.as_str() {
+        "direct" => Some(msi::LED_MODE_STATIC),
+        "off" => Some(msi::LED_MODE_DISABLE),
+        _ => MsiLedMode::from_str(&key, true).ok().map(|mode| mode as u8),
+    }
+}
+
+fn msi_import_profile(path: &Path) -> Result<()> {
+    let profile = openrgb::Profile::load(path).map_err(LightsOutError::Config)?;
+    let controller = profile
+        .controllers
+        .iter()
+        .find(|c| c.name.to_ascii_lowercase().contains("msi"))
+        .ok_or_else(|| {
+            LightsOutError::Config(anyhow!("No MSI controller in {}", path.display()))
+        })?;
+    println!(
+        "  Importing {:?} (OpenRGB profile version {})",
+        controller.name, profile.version
+    );
+
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = msi_open(&api)?;
+    let mut buf = msi_read_led_report(&device)?;
+
+    for zone in &controller.zones {
+        let zones = msi_zones_for_openrgb_name(&zone.name);
+        if zones.is_empty() {
+            eprintln!("  Warning: Skipping unknown zone {:?}", zone.name);
+            continue;
+        }
+        let mode = controller.zone_mode(zone);
+        let Some(mode_byte) = mode
+            .as_ref()
+            .and_then(|m| msi_mode_for_openrgb_name(&m.name))
+        else {
+            eprintln!(
+                "  Warning: Skipping zone {:?} with unknown mode {:?}",
+                zone.name,
+                mode.map(|m| m.name)
+            );
+            continue;
+        };
+        let rgb = match controller.zone_color(zone, mode.as_ref()) {
+            Some(color) => color.to_rgb().map_err(LightsOutError::Config)?,
+            None => Rgb::BLACK,
+        };
+        let speed = mode.as_ref().and_then(|m| m.speed);
+
+        for zone_index in zones {
+            let offset = msi::LED_OFFSETS[zone_index];
+            msi_set_zone(&mut buf, offset, mode_byte, rgb);
+            if let Some(speed) = speed {
+                let flags = &mut buf[offset + msi::ZONE_SPEED_BRIGHTNESS];
+                let speed = speed.min(msi::LED_SPEED_HIGH as u32) as u8;
+                *flags = (*flags & !msi::LED_SPEED_MASK) | speed;
+            }
+        }
+        println!(
+            "    {}: {} {}",
+            zone.name,
+            msi_led_mode_name(mode_byte),
+            rgb
+        );
+    }
+
+    msi_write_led_report(&device, &buf)?;
+    println!("  MSI CORELIQUID: Profile applied");
+    Ok(())
+}
+
+/// OpenRGB-style name for an MSI mode byte ("double-flashing" -> "Double Flashing")
+fn openrgb_mode_name(mode: u8) -> String {
+    if mode == msi::LED_MODE_DISABLE {
+        return "Off".to_string();
+    }
+    msi_led_mode_name(mode)
+        .split('-')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or_else(String::new, |first| {
+                first.to_ascii_uppercase().to_string() + chars.as_str()
+            })
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Format version written by `msi eeprom-backup`
+const MSI_BACKUP_VERSION: u32 = 1;
+
+/// Cooler settings saved by `msi eeprom-backup`
+///
+/// `report` and `fan_mode` are what gets restored; `zones` is a readable
+/// copy of the zone settings in the report.
+#[derive(Debug, Serialize, Deserialize)]
+struct MsiBackup {
+    version: u32,
+    /// Unix time of the backup
+    timestamp: u64,
+    model: String,
+    /// USB device release number (bcdDevice), which tracks the firmware
+    firmware: u16,
+    fan_mode: Option<FanMode>,
+    zones: Vec<MsiBackupZone>,
+    /// LED feature report as hex
+    report: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MsiBackupZone {
+    zone: usize,
+    name: String,
+    mode: String,
+    color: Rgb,
+}
+
+/// Model name and firmware release of the interface `msi_open` uses
+fn msi_device_identity(api: &HidApi) -> Result<(String, u16)> {
+    let info = msi_selected_interface(api)?;
+    let model = info
+        .product_string()
+        .unwrap_or("MSI CORELIQUID")
+        .to_string();
+    Ok((model, info.release_number()))
+}
+
+fn msi_eeprom_backup(out: &Path) -> Result<()> {
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = msi_open(&api)?;
+    let (model, firmware) = msi_device_identity(&api)?;
+
+    let buf = msi_read_led_report(&device)?;
+    let fan_mode = match msi_read_fan_mode(&device) {
+        Ok(mode) => Some(mode),
+        Err(e) => {
+            eprintln!("  Warning: Fan mode not saved: {:#}", e);
+            None
+        }
+    };
+    let zones = msi::LED_OFFSETS
+        .iter()
+        .zip(msi::ZONE_MAP)
+        .enumerate()
+        .map(|(zone, (&offset, (name, _)))| MsiBackupZone {
+            zone,
+            name: name.to_string(),
+            mode: msi_led_mode_name(buf[offset]),
+            color: Rgb::new(
+                buf[offset + msi::ZONE_COLOR],
+                buf[offset + msi::ZONE_COLOR + 1],
+                buf[offset + msi::ZONE_COLOR + 2],
+            ),
+        })
+        .collect();
+    let backup = MsiBackup {
+        version: MSI_BACKUP_VERSION,
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        model,
+        firmware,
+        fan_mode,
+        zones,
+        report: buf.iter().map(|b| format!("{:02x}", b)).collect(),
+    };
+
+    let content = serde_json::to_string_pretty(&backup)? + "\n";
+    write_atomic(out, content.as_bytes())?;
+    println!(
+        "  MSI CORELIQUID: Saved settings of {} (firmware {:04x}) to {}",
+        backup.model,
+        backup.firmware,
+        out.display()
+    );
+    Ok(())
+}
+
+/// USB release number (bcdDevice) as "x.yy"; it is BCD, so 0x0108 = "1.08"
+fn usb_release_version(release: u16) -> String {
+    format!("{:x}.{:02x}", release >> 8, release & 0xff)
+}
+
+/// One entry of the release table (`msi::FIRMWARE_DB` or msi-firmware.json)
+#[derive(Debug, Deserialize)]
+struct FirmwareNote {
+    version: String,
+    notes: String,
+    compatible: bool,
+}
+
+impl FirmwareNote {
+    fn builtin() -> Vec<FirmwareNote> {
+        msi::FIRMWARE_DB
+            .iter()
+            .map(|&(version, notes, compatible)| FirmwareNote {
+                version: version.to_string(),
+                notes: notes.to_string(),
+                compatible,
+            })
+            .collect()
+    }
+
+    fn fetch() -> Result<Vec<FirmwareNote>> {
+        let body = ureq::get(msi::FIRMWARE_DB_URL)
+            .timeout(Duration::from_secs(msi::FIRMWARE_DB_TIMEOUT_SECS))
+            .call()
+            .with_context(|| format!("Failed to fetch {}", msi::FIRMWARE_DB_URL))?
+            .into_string()
+            .context("Failed to read firmware table")?;
+        serde_json::from_str(&body).context("Failed to parse firmware table")
+    }
+
+    /// (major, minor) for ordering; None for versions that aren't "x.y"
+    fn key(version: &str) -> Option<(u32, u32)> {
+        let (major, minor) = version.split_once('.')?;
+        Some((major.parse().ok()?, minor.parse().ok()?))
+    }
+}
+
+fn msi_firmware_update_check(online: bool) -> Result<()> {
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let (model, release) = msi_device_identity(&api)?;
+    let version = usb_release_version(release);
+
+    let db = if online {
+        FirmwareNote::fetch().unwrap_or_else(|e| {
+            eprintln!("  Warning: {:#}; using the built-in table", e);
+            FirmwareNote::builtin()
+        })
+    } else {
+        FirmwareNote::builtin()
+    };
+
+    println!("  {}: USB release (bcdDevice) {}", model, version);
+    match db.iter().find(|note| note.version == version) {
+        Some(note) => {
+            println!("  {}", note.notes);
+            if !note.compatible {
+                println!("  Warning: this release is known not to work with lights-out");
+            }
+        }
+        None => println!("  No notes for this release"),
+    }
+    let newest = db
+        .iter()
+        .filter_map(|note| FirmwareNote::key(&note.version).map(|key| (key, note)))
+        .max_by_key(|&(key, _)| key);
+    if let Some((key, note)) = newest {
+        if FirmwareNote::key(&version).is_some_and(|current| current < key) {
+            println!("  Newer release known: {}", note.version);
+        }
+    }
+    Ok(())
+}
+
+fn msi_eeprom_restore(input: &Path, force: bool) -> Result<()> {
+    let content =
+        fs::read_to_string(input).with_context(|| format!("Failed to read {}", input.display()))?;
+    let backup: MsiBackup = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", input.display()))
+        .map_err(LightsOutError::Config)?;
+    if backup.version > MSI_BACKUP_VERSION {
+        return Err(LightsOutError::Config(anyhow!(
+            "{} has backup version {}, this build supports up to {}",
+            input.display(),
+            backup.version,
+            MSI_BACKUP_VERSION
+        ))
+        .into());
+    }
+    let report =
+        parse_hex_bytes(std::slice::from_ref(&backup.report)).map_err(LightsOutError::Config)?;
+    let report: [u8; msi::MAX_DATA_LEN] = report.as_slice().try_into().map_err(|_| {
+        LightsOutError::Config(anyhow!(
+            "Backup report is {} bytes, expected {}",
+            report.len(),
+            msi::MAX_DATA_LEN
+        ))
+    })?;
+
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = msi_open(&api)?;
+    let (model, firmware) = msi_device_identity(&api)?;
+    // The report layout can change between firmware releases
+    if firmware != backup.firmware && !force {
+        return Err(LightsOutError::Config(anyhow!(
+            "Backup is from firmware {:04x} but the cooler runs {:04x} \
+             (use --force to restore anyway)",
+            backup.firmware,
+            firmware
+        ))
+        .into());
+    }
+
+    msi_write_led_report(&device, &report)?;
+    if let Some(mode) = backup.fan_mode {
+        msi_write_fan_mode(&device, mode)?;
+    }
+    println!(
+        "  MSI CORELIQUID: Restored {} settings from {}",
+        model,
+        input.display()
+    );
+    Ok(())
+}
+
+fn msi_export_profile(format: ExportFormat, out: &Path) -> Result<()> {
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = msi_open(&api)?;
+    let buf = msi_read_led_report(&device)?;
+
+    let profile = match format {
+        ExportFormat::Openrgb => {
+            let zones: Vec<openrgb::Zone> = msi::LED_OFFSETS
+                .iter()
+                .zip(msi::ZONE_MAP)
+                .map(|(&offset, (name, _))| {
+                    let rgb = Rgb::new(
+                        buf[offset + msi::ZONE_COLOR],
+                        buf[offset + msi::ZONE_COLOR + 1],
+                        buf[offset + msi::ZONE_COLOR + 2],
+                    );
+                    let speed = buf[offset + msi::ZONE_SPEED_BRIGHTNESS] & msi::LED_SPEED_MASK;
+                    openrgb::Zone {
+                        name: name.to_string(),
+                        mode: Some(openrgb::Mode {
+                            name: openrgb_mode_name(buf[offset]),
+                            speed: Some(speed as u32),
+                            colors: vec![openrgb::Color::from_rgb(rgb)],
+                        }),
+                        colors: vec![openrgb::Color::from_rgb(rgb)],
+                    }
+                })
+                .collect();
+            openrgb::Profile {
+                version: openrgb::Profile::VERSION,
+                controllers: vec![openrgb::Controller {
+                    name: "MSI MPG CORELIQUID".to_string(),
+                    num_zones: zones.len(),
+                    active_mode: 0,
+                    modes: Vec::new(),
+                    zones,
+                    colors: Vec::new(),
+                }],
+            }
+        }
+    };
+    profile.save(out)?;
+
+    println!("  MSI CORELIQUID: Profile exported to {}", out.display());
+    Ok(())
+}
+
+fn msi_led_zone_map() {
+    for (zone, (&offset, (name, description))) in
+        msi::LED_OFFSETS.iter().zip(msi::ZONE_MAP).enumerate()
+    {
+        let location = if msi::PUMP_HEAD_ZONES.contains(&zone) {
+            "[pump head]"
+        } else if msi::RADIATOR_FAN_ZONES.contains(&zone) {
+            "[radiator fans]"
+        } else {
+            ""
+        };
+        let line = format!(
+            "Zone {:2} (offset {:3}): {:<14} {} {}",
+            zone, offset, name, description, location
+        );
+        println!("{}", line.trim_end());
+    }
+}
+
+/// Set a subset of zones to a static color, leaving the others as they are
+fn msi_set_zone_group(zones: &[usize], group: &str, rgb: Rgb) -> Result<()> {
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = msi_open(&api)?;
+
+    let mut buf = msi_read_led_report(&device)?;
+    for &zone in zones {
+        msi_set_zone(&mut buf, msi::LED_OFFSETS[zone], msi::LED_MODE_STATIC, rgb);
+    }
+    msi_write_led_report(&device, &buf)?;
+
+    println!("  MSI CORELIQUID: {} set to {}", group, rgb);
+    Ok(())
+}
+
+fn msi_led_colormap(name: &str) -> Result<()> {
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = msi_open(&api)?;
+
+    let mut buf = msi_read_led_report(&device)?;
+    let last = (msi::LED_OFFSETS.len() - 1) as f32;
+    for (zone, &offset) in msi::LED_OFFSETS.iter().enumerate() {
+        let rgb = colormap_lookup(name, zone as f32 / last)
+            .ok_or_else(|| LightsOutError::Config(anyhow!("Unknown colormap {}", name)))?;
+        msi_set_zone(&mut buf, offset, msi::LED_MODE_STATIC, rgb);
+    }
+    msi_write_led_report(&device, &buf)?;
+
+    println!("  MSI CORELIQUID: Zones set to the {} colormap", name);
+    Ok(())
+}
+
+/// Set every zone to the same mode and color in a single feature report write
+fn msi_set_all_leds(device: &HidDevice, mode: u8, rgb: Rgb) -> Result<()> {
+    let mut buf = msi_read_led_report(device)?;
+    msi_fill_zones(&mut buf, mode, rgb);
+    msi_write_led_report(device, &buf)
+}
+
+fn msi_led_all(mode: u8, rgb: Rgb) -> Result<()> {
+    if MsiLedMode::from_byte(mode).is_none() {
+        return Err(LightsOutError::Config(anyhow!(
+            "Unknown MSI LED mode {} (see `msi led-mode --help`)",
+            mode
+        ))
+        .into());
+    }
+
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = msi_open(&api)?;
+    msi_set_all_leds(&device, mode, rgb)?;
+
+    println!(
+        "  MSI CORELIQUID: All zones set to {} {}",
+        msi_led_mode_name(mode),
+        rgb
+    );
+    Ok(())
+}
+
+fn msi_lcd_disable(device: &HidDevice) -> Result<()> {
+    let mut cmd = [0u8; msi::HID_REPORT_LEN];
+    cmd[0] = msi::CMD_PREFIX;
+    cmd[1] = msi::CMD_LCD_DISABLE;
+    device.write(&cmd).context("Failed to disable LCD")?;
+    Ok(())
+}
+
+fn msi_set_fan_mode(mode: FanMode) -> Result<()> {
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = msi_open(&api)?;
+
+    msi_write_fan_mode(&device, mode)?;
+
+    println!("  MSI CORELIQUID: Fan mode set to {:?}", mode);
+    Ok(())
+}
+
+fn msi_write_fan_mode(device: &HidDevice, mode: FanMode) -> Result<()> {
+    let mode_val = mode as u8;
+
+    // Build command buffer with mode at specific offsets
+    let mut buf = [0u8; msi::HID_REPORT_LEN];
+    buf[0] = msi::CMD_PREFIX;
+    buf[1] = msi::CMD_FAN_MODE_1;
+    for &offset in msi::FAN_MODE_OFFSETS {
+        buf[offset] = mode_val;
+    }
+
+    // Send first command (0x40)
+    device
+        .write(&buf)
+        .context("Failed to write fan mode command 0x40")?;
+
+    // Send second command (0x41)
+    buf[1] = msi::CMD_FAN_MODE_2;
+    device
+        .write(&buf)
+        .context("Failed to write fan mode command 0x41")?;
+
+    Ok(())
+}
+
+/// Find the CPU temperature sensor in /sys/class/hwmon
+/// Looks for k10temp (AMD) or coretemp (Intel) chips
+fn find_cpu_temp_path(tdie: bool) -> Result<std::path::PathBuf> {
+    if tdie {
+        return find_hwmon_labeled_temp_path("k10temp", "Tdie")?.ok_or_else(|| {
+            LightsOutError::DeviceNotFound(anyhow!(
+                "No k10temp sensor labeled Tdie (newer kernels only report it on CPUs \
+                 whose Tctl is offset; use --temp-offset otherwise)"
+            ))
+            .into()
+        });
+    }
+    // AMD CPUs use k10temp, Intel uses coretemp
+    // For k10temp, Tctl is usually temp1_input
+    // For coretemp, package temp is also temp1_input
+    find_hwmon_temp_path(&["k10temp", "coretemp"])?.ok_or_else(|| {
+        LightsOutError::DeviceNotFound(anyhow!(
+            "CPU temperature sensor not found (looking for k10temp or coretemp)"
+        ))
+        .into()
+    })
+}
+
+/// temp1_input of the first hwmon chip whose name is one of `names`
+fn find_hwmon_temp_path(names: &[&str]) -> Result<Option<std::path::PathBuf>> {
+    let hwmon_path = Path::new("/sys/class/hwmon");
+
+    for entry in fs::read_dir(hwmon_path).context("Failed to read /sys/class/hwmon")? {
+        let entry = entry?;
+        let name_path = entry.path().join("name");
+
+        if let Ok(name) = fs::read_to_string(&name_path) {
+            if names.contains(&name.trim()) {
+                let temp_path = entry.path().join("temp1_input");
+                if temp_path.exists() {
+                    return Ok(Some(temp_path));
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// temp*_input of the `name` hwmon chip whose temp*_label is `label`
+fn find_hwmon_labeled_temp_path(name: &str, label: &str) -> Result<Option<std::path::PathBuf>> {
+    let hwmon_path = Path::new("/sys/class/hwmon");
+
+    for entry in fs::read_dir(hwmon_path).context("Failed to read /sys/class/hwmon")? {
+        let dir = entry?.path();
+        if fs::read_to_string(dir.join("name")).is_ok_and(|n| n.trim() == name) {
+            for file in fs::read_dir(&dir)?.flatten() {
+                let file_name = file.file_name().to_string_lossy().into_owned();
+                let Some(sensor) = file_name.strip_suffix("_label") else {
+                    continue;
+                };
+                if fs::read_to_string(file.path()).is_ok_and(|l| l.trim() == label) {
+                    return Ok(Some(dir.join(format!("{}_input", sensor))));
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// sysfs paths of an amdgpu card's sensors
+struct GpuSensors {
+    /// amdgpu hwmon edge temperature
+    temp_path: Option<std::path::PathBuf>,
+    /// /sys/class/drm/cardN/device
+    device_dir: std::path::PathBuf,
+}
+
+/// GPU readings shown by the daemon with --gpu-metrics
+#[derive(Debug, Clone, Copy)]
+struct GpuMetrics {
+    temp: i32,
+    freq_mhz: u32,
+    util_pct: u8,
+}
+
+/// Everything the daemon measured in one cycle
+#[derive(Debug, Clone, Copy)]
+struct DaemonMetrics {
+    cpu_temp: i32,
+    gpu: Option<GpuMetrics>,
+    unit: TempUnit,
+}
+
+impl fmt::Display for DaemonMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CPU Temperature: {}", self.unit.show(self.cpu_temp))?;
+        if let Some(gpu) = self.gpu {
+            write!(
+                f,
+                "  GPU: {}, {} MHz, {}% busy",
+                self.unit.show(gpu.temp),
+                gpu.freq_mhz,
+                gpu.util_pct
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Unit the daemon prints temperatures in; everything sent or written stays in °C
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TempUnit {
+    Celsius,
+    Fahrenheit,
+}
+
+impl TempUnit {
+    fn from_args(args: &DaemonArgs) -> TempUnit {
+        if args.temp_fahrenheit {
+            TempUnit::Fahrenheit
+        } else {
+            TempUnit::Celsius
+        }
+    }
+
+    /// A temperature in °C shown in this unit, e.g. "45°C" or "113°F"
+    fn show(self, celsius: i32) -> String {
+        match self {
+            TempUnit::Celsius => format!("{}°C", celsius),
+            TempUnit::Fahrenheit => format!("{}°F", celsius_to_fahrenheit(celsius)),
+        }
+    }
+
+    /// Like `show`, with one decimal (for averages)
+    fn show_f64(self, celsius: f64) -> String {
+        match self {
+            TempUnit::Celsius => format!("{:.1}°C", celsius),
+            TempUnit::Fahrenheit => format!("{:.1}°F", celsius * 1.8 + 32.0),
+        }
+    }
+}
+
+fn celsius_to_fahrenheit(c: i32) -> i32 {
+    (c as f64 * 1.8 + 32.0).round() as i32
+}
+
+impl GpuSensors {
+    /// Find the first amdgpu card exposing pp_dpm_sclk
+    fn find() -> Result<GpuSensors> {
+        let drm_path = Path::new("/sys/class/drm");
+        for entry in fs::read_dir(drm_path).context("Failed to read /sys/class/drm")? {
+            let entry = entry?;
+            let name = entry.file_name();
+            // cardN only, not connectors like card0-DP-1
+            if !name
+                .to_string_lossy()
+                .strip_prefix("card")
+                .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+            {
+                continue;
+            }
+            let device_dir = entry.path().join("device");
+            if device_dir.join("pp_dpm_sclk").exists() {
+                return Ok(GpuSensors {
+                    temp_path: find_hwmon_temp_path(&["amdgpu"])?,
+                    device_dir,
+                });
+            }
+        }
+        Err(LightsOutError::DeviceNotFound(anyhow!(
+            "No amdgpu card with pp_dpm_sclk found in /sys/class/drm"
+        ))
+        .into())
+    }
+
+    fn read(&self) -> Result<GpuMetrics> {
+        let temp = match &self.temp_path {
+            Some(path) => read_cpu_temp(path)?,
+            None => 0,
+        };
+
+        // Current shader clock level is marked with '*', e.g. "1: 1800Mhz *"
+        let sclk = fs::read_to_string(self.device_dir.join("pp_dpm_sclk"))
+            .context("Failed to read pp_dpm_sclk")?;
+        let freq_mhz = sclk
+            .lines()
+            .find(|line| line.trim_end().ends_with('*'))
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|freq| freq.to_ascii_lowercase().strip_suffix("mhz")?.parse().ok())
+            .unwrap_or(0);
+
+        let util_pct = fs::read_to_string(self.device_dir.join("gpu_busy_percent"))
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(0);
+
+        Ok(GpuMetrics {
+            temp,
+            freq_mhz,
+            util_pct,
+        })
+    }
+}
+
+/// Check that a user-supplied sensor file reads as a sane millidegree value
+fn validate_temp_sensor(path: &Path) -> Result<()> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read temperature sensor {}", path.display()))?;
+    let millidegrees: i64 = content.trim().parse().map_err(|_| {
+        LightsOutError::Config(anyhow!(
+            "{} does not contain an integer temperature: {:?}",
+            path.display(),
+            content.trim()
+        ))
+    })?;
+    if !(0..=msi::MAX_SANE_MILLIDEGREES).contains(&millidegrees) {
+        return Err(LightsOutError::Config(anyhow!(
+            "{} reads {}, expected millidegrees C between 0 and {}",
+            path.display(),
+            millidegrees,
+            msi::MAX_SANE_MILLIDEGREES
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Read CPU temperature in degrees Celsius
+fn read_cpu_temp(temp_path: &Path) -> Result<i32> {
+    let content = fs::read_to_string(temp_path).context("Failed to read temperature")?;
+    let millidegrees: i32 = content
+        .trim()
+        .parse()
+        .context("Failed to parse temperature")?;
+    Ok(millidegrees / 1000)
+}
+
+/// Send CPU temperature to the AIO
+fn send_cpu_temp(device: &HidDevice, temp: i32) -> Result<()> {
+    device
+        .write(&cpu_temp_packet(temp))
+        .context("Failed to send CPU temperature")?;
+    Ok(())
+}
+
+/// CPU status packet carrying a temperature for Smart mode
+fn cpu_temp_packet(temp: i32) -> [u8; msi::HID_REPORT_LEN] {
+    let mut buf = [0u8; msi::HID_REPORT_LEN];
+    buf[0] = msi::CMD_PREFIX;
+    buf[1] = msi::CMD_CPU_STATUS;
+
+    // Dummy CPU frequency (the AIO doesn't actually use this)
+    let freq: u16 = 3000;
+    buf[2] = (freq & 0xFF) as u8;
+    buf[3] = ((freq >> 8) & 0xFF) as u8;
+
+    // CPU temperature (little-endian)
+    buf[4] = (temp & 0xFF) as u8;
+    buf[5] = ((temp >> 8) & 0xFF) as u8;
+    buf
+}
+
+/// Send a fixed temperature to exercise the Smart mode fan curve without load
+fn msi_test_temp_send(temp: i32, count: u32, interval: Duration, verbose: bool) -> Result<()> {
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = msi_open(&api)?;
+    let stop_flag = install_stop_handler()?;
+
+    let packet = cpu_temp_packet(temp);
+    for i in 0..count {
+        if stop_flag.load(Ordering::Relaxed) {
+            break;
+        }
+        if i > 0 {
+            sleep_unless_stopped(&stop_flag, interval);
+            if stop_flag.load(Ordering::Relaxed) {
+                break;
+            }
+        }
+        if verbose {
+            print_hex_dump(&packet);
+        }
+        device
+            .write(&packet)
+            .context("Failed to send CPU temperature")?;
+        println!("  MSI CORELIQUID: Sent {}°C ({}/{})", temp, i + 1, count);
+    }
+    Ok(())
+}
+
+/// Run the daemon in the foreground or background per --daemon-mode
+fn run_daemon(args: &DaemonArgs, simulation: Option<TempSimulation>) -> Result<()> {
+    let pid_file = args.pid_file.clone().unwrap_or_else(daemon_pid_path);
+    if args.pid_check && is_daemon_running(&pid_file) {
+        if !args.force {
+            return Err(LightsOutError::Io(anyhow!(
+                "A daemon is already running (PID file {}); stop it with `daemon stop` \
+                 or pass --force",
+                pid_file.display()
+            ))
+            .into());
+        }
+        daemon_stop(&pid_file)?;
+    }
+
+    let writes_pid_file = args.daemon_mode == DaemonMode::Background || args.pid_check;
+    if args.daemon_mode == DaemonMode::Background {
+        daemonize(&pid_file)?;
+    } else if args.pid_check {
+        write_atomic(&pid_file, format!("{}\n", std::process::id()).as_bytes())?;
+    }
+    let stop_flag = install_stop_handler()?;
+    let result = msi_daemon(args, simulation, stop_flag);
+    if writes_pid_file {
+        let _ = fs::remove_file(&pid_file);
+    }
+    result
+}
+
+/// Whether the PID file names a live lights-out process
+///
+/// A PID file left behind by a daemon that is gone (or whose PID now belongs
+/// to another program) is removed.
+fn is_daemon_running(pid_file: &Path) -> bool {
+    let Ok(content) = fs::read_to_string(pid_file) else {
+        return false;
+    };
+    let running = content
+        .trim()
+        .parse::<u32>()
+        .is_ok_and(|pid| pid != std::process::id() && is_lights_out_process(pid));
+    if !running && fs::remove_file(pid_file).is_ok() {
+        println!("  Removed stale PID file {}", pid_file.display());
+    }
+    running
+}
+
+/// Whether `pid` is another instance of this program
+///
+/// comm is the executable name (up to 15 characters), so compare it with ours.
+fn is_lights_out_process(pid: u32) -> bool {
+    let comm = |pid: &str| fs::read_to_string(format!("/proc/{}/comm", pid)).ok();
+    comm(&pid.to_string()).is_some_and(|c| comm("self") == Some(c))
+}
+
+/// Run the temperature monitoring daemon
+fn msi_daemon(
+    args: &DaemonArgs,
+    simulation: Option<TempSimulation>,
+    stop_flag: Arc<AtomicBool>,
+) -> Result<()> {
+    // Find the CPU temperature sensor (before opening the device, so a bad
+    // --secondary-sensor fails fast)
+    let temp_source = match (simulation, &args.secondary_sensor) {
+        (Some(simulation), _) => TempSource::Simulated(simulation, Instant::now()),
+        (None, Some(path)) => {
+            validate_temp_sensor(path)?;
+            TempSource::Sensor(path.clone())
+        }
+        (None, None) => TempSource::Sensor(find_cpu_temp_path(args.cpu_tdie)?),
+    };
+    let unit = TempUnit::from_args(args);
+    let mut alert_commands = AlertCommands::new(args)?;
+    let mut auto_fan = HysteresisController::new(args)?;
+
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let devices = if args.multi_device {
+        let devices = msi_open_all(&api)?;
+        println!("  Driving {} MSI CORELIQUID unit(s)", devices.len());
+        devices
+    } else {
+        vec![msi_open(&api)?]
+    };
+    let device = &devices[0];
+    match &temp_source {
+        TempSource::Sensor(path) => println!("  Using CPU temp sensor: {}", path.display()),
+        TempSource::Simulated(simulation, _) => {
+            println!(
+                "  Simulating CPU temperature: {}",
+                simulation.pattern.describe(unit)
+            )
+        }
+    }
+    if args.temp_offset != 0 {
+        println!("  Sending temperature offset by {:+}°C", args.temp_offset);
+    }
+    let speed_multiplier = Config::load()?.msi.fan_speed_multiplier;
+    if let Some(multiplier) = speed_multiplier {
+        println!(
+            "  Sending temperature x{} (fan speed-multiplier)",
+            multiplier
+        );
+    }
+    if let Some(auto_fan) = &auto_fan {
+        for device in &devices {
+            msi_write_fan_mode(device, auto_fan.current_mode)?;
+        }
+        println!(
+            "  Auto fan: {:?} below {}, {:?} above {}",
+            args.auto_fan_low,
+            unit.show(auto_fan.down_threshold),
+            args.auto_fan_high,
+            unit.show(auto_fan.up_threshold)
+        );
+    }
+    if let Some(pct) = args.min_pump {
+        for device in &devices {
+            msi_set_pump_floor(device, pct)?;
+        }
+        println!("  Pump speed held at or above {}%", pct);
+    }
+    let gpu_sensors = if args.gpu_metrics {
+        let sensors = GpuSensors::find()?;
+        println!("  Found GPU: {}", sensors.device_dir.display());
+        Some(sensors)
+    } else {
+        None
+    };
+    println!("  Starting temperature monitoring (Ctrl+C to stop)...");
+
+    // Last color written by the LED effect, to skip redundant writes
+    let mut effect_color: Option<Rgb> = None;
+    let mut wallpaper = WallpaperWatcher::default();
+    let mut alert = OverheatAlert::new(args);
+
+    // Only print every Nth cycle when --report-interval is longer than --interval
+    let report_every_n_cycles = match args.report_interval {
+        Some(report) => {
+            let interval = args.interval.as_secs_f64().max(f64::EPSILON);
+            ((report.as_secs_f64() / interval).round() as u64).max(1)
+        }
+        None => 1,
+    };
+    let mut cycle_count: u64 = 0;
+
+    let measure_interval = args.measure_interval.unwrap_or(args.interval);
+    let mut last_measured_temp: Option<i32> = None;
+    let mut last_measured_at: Option<Instant> = None;
+    let mut average = args
+        .cpu_temp_average
+        .map(|samples| MovingAverage::<{ msi::MAX_TEMP_AVERAGE_SAMPLES }>::new(samples as usize));
+    let mut last_averaged_temp: Option<i32> = None;
+    let mut stats = StatsSummary::new();
+    let mut post_command = args.post_command.clone().map(PostCommand::new);
+
+    let server_metrics = match args.metrics_server {
+        Some(port) => {
+            let shared = Arc::new(std::sync::Mutex::new(metrics::MetricsSnapshot::new()));
+            metrics::spawn_server(args.metrics_bind, port, shared.clone())?;
+            println!(
+                "  Serving metrics on {} at /metrics",
+                std::net::SocketAddr::new(args.metrics_bind, port)
+            );
+            Some(shared)
+        }
+        None => None,
+    };
+    let influx = match (args.export_metrics, &args.url, &args.token, &args.bucket) {
+        (Some(MetricsExport::Influxdb), Some(url), Some(token), Some(bucket)) => {
+            println!("  Exporting metrics to InfluxDB at {}", url);
+            Some(metrics::InfluxExporter::new(
+                url,
+                token,
+                bucket,
+                args.org.as_deref(),
+            ))
+        }
+        _ => None,
+    };
+
+    let notifier = systemd::SystemdNotifier::from_env()?;
+    if args.watchdog {
+        match (&notifier, systemd::SystemdNotifier::watchdog_timeout()) {
+            (None, _) => eprintln!("  Warning: --watchdog given but NOTIFY_SOCKET is not set"),
+            (Some(_), Some(timeout)) if args.interval * 2 > timeout => eprintln!(
+                "  Warning: --interval {:?} is over half the watchdog timeout {:?}",
+                args.interval, timeout
+            ),
+            _ => {}
+        }
+    }
+    if let Some(notifier) = &notifier {
+        if let Err(e) = notifier.ready() {
+            eprintln!("  Warning: {:#}", e);
+        }
+    }
+
+    // Main loop
+    while !stop_flag.load(Ordering::Relaxed) {
+        if last_measured_at.is_none_or(|at| at.elapsed() >= measure_interval) {
+            last_measured_at = Some(Instant::now());
+            match temp_source.read() {
+                Ok(temp) => {
+                    last_measured_temp = Some(temp);
+                    last_averaged_temp = Some(average.as_mut().map_or(temp, |avg| avg.push(temp)));
+                    stats.record(temp);
+                    if let Some(post_command) = &mut post_command {
+                        post_command.run(temp);
+                    }
+                    alert_commands.check(temp);
+                    if let Some((mode, held)) = auto_fan.as_mut().and_then(|ctl| ctl.update(temp)) {
+                        println!(
+                            "  Auto fan: CPU at {} for {}s, switching to {:?}",
+                            unit.show(temp),
+                            held.as_secs(),
+                            mode
+                        );
+                        for device in &devices {
+                            if let Err(e) = msi_write_fan_mode(device, mode) {
+                                eprintln!("  Warning: Failed to set fan mode: {}", e);
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    eprintln!("  Warning: Failed to read temperature: {}", e);
+                    stats.record_failure();
+                }
+            }
+
+            if server_metrics.is_some() || influx.is_some() {
+                // Fan speeds are only read back for metrics
+                let fans = msi_read_fan_status(device)
+                    .map_err(|e| eprintln!("  Warning: Failed to read fan status: {}", e))
+                    .ok();
+                if let Some(Ok(mut m)) = server_metrics.as_ref().map(|shared| shared.lock()) {
+                    m.cpu_temp_celsius = last_measured_temp;
+                    m.temp_read_failures = stats.total_failures;
+                    if let Some(fans) = &fans {
+                        m.fan_rpm = fans.iter().map(|f| (f.name, f.rpm)).collect();
+                    }
+                }
+                if let Some(influx) = &influx {
+                    let pump_rpm = fans
+                        .iter()
+                        .flatten()
+                        .find(|f| f.name == msi::FAN_NAMES[msi::PUMP_CHANNEL])
+                        .map(|f| f.rpm);
+                    let fan_mode = msi_read_fan_mode(device)
+                        .ok()
+                        .and_then(|mode| mode.to_possible_value())
+                        .map(|v| v.get_name().to_string());
+                    if let Some(line) =
+                        influx.line(last_measured_temp, pump_rpm, fan_mode.as_deref())
+                    {
+                        influx.push(line);
+                    }
+                }
+            }
+        }
+
+        if let Some(stats_interval) = args.stats_interval {
+            if stats.period_start.elapsed() >= stats_interval {
+                stats.report(unit);
+                stats.reset();
+            }
+        }
+
+        if let Some(temp) = last_measured_temp {
+            if cycle_count.is_multiple_of(report_every_n_cycles) {
+                let gpu = gpu_sensors.as_ref().and_then(|sensors| {
+                    sensors
+                        .read()
+                        .map_err(|e| eprintln!("  Warning: Failed to read GPU metrics: {}", e))
+                        .ok()
+                });
+                let metrics = DaemonMetrics {
+                    cpu_temp: temp,
+                    gpu,
+                    unit,
+                };
+                println!("  {}", metrics);
+            }
+            let sent = msi_scaled_temp(
+                last_averaged_temp.unwrap_or(temp) + args.temp_offset,
+                speed_multiplier,
+            );
+            for device in &devices {
+                if let Err(e) = send_cpu_temp(device, sent) {
+                    eprintln!("  Warning: Failed to send temperature: {}", e);
+                }
+            }
+            if let Some(path) = &args.write_temp_to_file {
+                if let Err(e) = write_atomic(path, format!("{}\n", temp).as_bytes()) {
+                    eprintln!("  Warning: Failed to write temperature file: {:#}", e);
+                }
+            }
+        }
+
+        if let (Some(alert), Some(temp)) = (alert.as_mut(), last_measured_temp) {
+            match alert.update(&devices, temp) {
+                // The restored report may not match the effect's last color
+                Ok(true) => effect_color = None,
+                Ok(false) => {}
+                Err(e) => eprintln!("  Warning: Failed to update overheat alert: {}", e),
+            }
+        }
+
+        // The alert owns the LEDs while it is active
+        let alert_active = alert.as_ref().is_some_and(OverheatAlert::is_active);
+        if let Some(effect) = args.effect.filter(|_| !alert_active) {
+            let temp = last_measured_temp;
+            let result = apply_daemon_effect(
+                &devices,
+                args,
+                effect,
+                temp,
+                &mut effect_color,
+                &mut wallpaper,
+            );
+            if let Err(e) = result {
+                eprintln!("  Warning: Failed to update LED effect: {}", e);
+            }
+        }
+
+        if let Some(notifier) = &notifier {
+            let status = match last_measured_temp {
+                Some(temp) => format!("Running, last temp: {}", unit.show(temp)),
+                None => "Running, no temperature yet".to_string(),
+            };
+            let mut result = notifier.status(&status);
+            if args.watchdog {
+                result = result.and_then(|()| notifier.watchdog_ping());
+            }
+            if let Err(e) = result {
+                eprintln!("  Warning: {:#}", e);
+            }
+        }
+
+        cycle_count += 1;
+
+        // Sleep for the interval, checking stop flag periodically
+        sleep_unless_stopped(&stop_flag, args.interval);
+    }
+
+    if let Some(alert) = alert.as_mut() {
+        if let Err(e) = alert.restore(&devices) {
+            eprintln!("  Warning: Failed to restore LEDs after alert: {}", e);
+        }
+    }
+    if let Some(notifier) = &notifier {
+        let _ = notifier.stopping();
+    }
+    for device in &devices {
+        args.shutdown().run(device);
+    }
+
+    println!("  Daemon stopped.");
+    Ok(())
+}
+
+/// Default PID file for `daemon --daemon-mode background`
+///
+/// Root (e.g. the system service) usually has no XDG_RUNTIME_DIR and must not
+/// fall back to the world-writable /tmp, where anyone could plant a PID.
+fn daemon_pid_path() -> std::path::PathBuf {
+    // SAFETY: geteuid() has no memory-safety requirements
+    let fallback = if unsafe { libc::geteuid() } == 0 {
+        "/run"
+    } else {
+        "/tmp"
+    };
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| std::path::PathBuf::from(fallback))
+        .join("lights-out-daemon.pid")
+}
+
+/// Detach from the terminal (double fork, new session) and write the PID file
+///
+/// Must run before any threads are started or devices opened.
+fn daemonize(pid_file: &Path) -> Result<()> {
+    let log_path = data_dir().join("daemon.log");
+    fs::create_dir_all(data_dir())
+        .with_context(|| format!("Failed to create {}", data_dir().display()))?;
+    let log = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .with_context(|| format!("Failed to open {}", log_path.display()))?;
+    let log_err = log.try_clone().context("Failed to duplicate log file")?;
+
+    println!(
+        "  Detaching; PID in {}, output in {}",
+        pid_file.display(),
+        log_path.display()
+    );
+    daemonize::Daemonize::new()
+        .pid_file(pid_file)
+        .working_directory("/")
+        .stdout(log)
+        .stderr(log_err)
+        .start()
+        .map_err(|e| anyhow!("Failed to start background daemon: {}", e))?;
+    Ok(())
+}
+
+/// Send SIGTERM to the daemon in `pid_file` and wait for it to exit
+fn daemon_stop(pid_file: &Path) -> Result<()> {
+    let content = match fs::read_to_string(pid_file) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(LightsOutError::DeviceNotFound(anyhow!(
+                "No daemon PID file at {}",
+                pid_file.display()
+            ))
+            .into())
+        }
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", pid_file.display())),
+    };
+    // Nonzero and unsigned so the PID can't signal a whole process group
+    let pid = content
+        .trim()
+        .parse::<std::num::NonZeroU32>()
+        .map_err(|e| {
+            LightsOutError::Config(anyhow!("Invalid PID file {}: {}", pid_file.display(), e))
+        })?
+        .get();
+
+    // Never signal a PID that isn't ours: the file may be stale or planted
+    if Path::new(&format!("/proc/{}", pid)).exists() && !is_lights_out_process(pid) {
+        return Err(LightsOutError::Permission(anyhow!(
+            "PID {} in {} is not a lights-out process; not signalling it",
+            pid,
+            pid_file.display()
+        ))
+        .into());
+    }
+
+    let pid = libc::pid_t::try_from(pid).map_err(|e| {
+        LightsOutError::Config(anyhow!("Invalid PID file {}: {}", pid_file.display(), e))
+    })?;
+    // SAFETY: kill() has no memory-safety requirements
+    if unsafe { libc::kill(pid, libc::SIGTERM) } != 0 {
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() == Some(libc::ESRCH) {
+            let _ = fs::remove_file(pid_file);
+            println!(
+                "  Daemon (PID {}) was not running; removed stale PID file",
+                pid
+            );
+            return Ok(());
+        }
+        return Err(err).with_context(|| format!("Failed to signal PID {}", pid));
+    }
+
+    let deadline = Instant::now() + Duration::from_millis(msi::DAEMON_STOP_TIMEOUT_MS);
+    // SAFETY: signal 0 only checks whether the process exists
+    while unsafe { libc::kill(pid, 0) } == 0 {
+        if Instant::now() > deadline {
+            return Err(LightsOutError::Io(anyhow!(
+                "Daemon (PID {}) did not exit after SIGTERM",
+                pid
+            ))
+            .into());
+        }
+        std::thread::sleep(Duration::from_millis(msi::DAEMON_POLL_MS));
+    }
+    println!("  Daemon (PID {}) stopped", pid);
+    Ok(())
+}
+
+/// --post-command runner; at most one instance runs at a time
+struct PostCommand {
+    command: String,
+    running: Option<(std::process::Child, Instant)>,
+    warned: bool,
+}
+
+impl PostCommand {
+    fn new(command: String) -> PostCommand {
+        PostCommand {
+            command,
+            running: None,
+            warned: false,
+        }
+    }
+
+    /// Start the command for a new temperature, unless the previous run is still going
+    fn run(&mut self, temp: i32) {
+        if let Some((child, started)) = &mut self.running {
+            match child.try_wait() {
+                Ok(Some(_)) | Err(_) => self.running = None,
+                Ok(None) => {
+                    if started.elapsed() > Duration::from_millis(msi::POST_COMMAND_WARN_MS)
+                        && !self.warned
+                    {
+                        eprintln!(
+                            "  Warning: --post-command has been running for over {} ms; \
+                             skipping runs until it exits",
+                            msi::POST_COMMAND_WARN_MS
+                        );
+                        self.warned = true;
+                    }
+                    return;
+                }
+            }
+        }
+
+        match std::process::Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .env("TEMP", temp.to_string())
+            .stdin(std::process::Stdio::null())
+            .spawn()
+        {
+            Ok(child) => {
+                self.running = Some((child, Instant::now()));
+                self.warned = false;
+            }
+            Err(e) => eprintln!("  Warning: Failed to run --post-command: {}", e),
+        }
+    }
+}
+
+/// --alert-command/--alert-threshold pairs
+struct AlertCommands {
+    tiers: Vec<AlertTier>,
+    cooldown: Duration,
+    unit: TempUnit,
+}
+
+struct AlertTier {
+    threshold: i32,
+    command: String,
+    last_run: Option<Instant>,
+    running: Option<std::process::Child>,
+}
+
+impl AlertCommands {
+    fn new(args: &DaemonArgs) -> Result<AlertCommands> {
+        if args.alert_commands.len() != args.alert_thresholds.len() {
+            return Err(LightsOutError::Config(anyhow!(
+                "Each --alert-command needs a matching --alert-threshold \
+                 ({} commands, {} thresholds)",
+                args.alert_commands.len(),
+                args.alert_thresholds.len()
+            ))
+            .into());
+        }
+        let tiers = args
+            .alert_commands
+            .iter()
+            .zip(&args.alert_thresholds)
+            .map(|(command, &threshold)| AlertTier {
+                threshold,
+                command: command.clone(),
+                last_run: None,
+                running: None,
+            })
+            .collect();
+        Ok(AlertCommands {
+            tiers,
+            cooldown: args.alert_cooldown,
+            unit: TempUnit::from_args(args),
+        })
+    }
+
+    /// Start the command of every tier at or below `temp` that is out of its cooldown
+    fn check(&mut self, temp: i32) {
+        for tier in &mut self.tiers {
+            // Reap the previous run so it doesn't linger as a zombie
+            if let Some(child) = &mut tier.running {
+                if !matches!(child.try_wait(), Ok(None)) {
+                    tier.running = None;
+                }
+            }
+            if temp < tier.threshold || tier.last_run.is_some_and(|at| at.elapsed() < self.cooldown)
+            {
+                continue;
+            }
+
+            println!(
+                "  Alert: CPU at {}, running --alert-command for {}",
+                self.unit.show(temp),
+                self.unit.show(tier.threshold)
+            );
+            tier.last_run = Some(Instant::now());
+            match std::process::Command::new("sh")
+                .arg("-c")
+                .arg(&tier.command)
+                .env("TEMP", temp.to_string())
+                .stdin(std::process::Stdio::null())
+                .spawn()
+            {
+                Ok(child) => tier.running = Some(child),
+                Err(e) => eprintln!("  Warning: Failed to run --alert-command: {}", e),
+            }
+        }
+    }
+}
+
+/// Two-mode fan switching for `daemon --auto-fan` with a dead band
+///
+/// The switch condition has to hold for `readings_required` readings in a
+/// row; a reading that breaks it starts the count over.
+struct HysteresisController {
+    up_threshold: i32,
+    down_threshold: i32,
+    low_mode: FanMode,
+    high_mode: FanMode,
+    current_mode: FanMode,
+    /// When the temperature first reached `up_threshold` in the current run
+    pending_up: Option<Instant>,
+    /// When the temperature first fell to `down_threshold` in the current run
+    pending_down: Option<Instant>,
+    pending_readings: u32,
+    readings_required: u32,
+}
+
+impl HysteresisController {
+    fn new(args: &DaemonArgs) -> Result<Option<HysteresisController>> {
+        let Some(temp) = args.auto_fan else {
+            return Ok(None);
+        };
+        if args.auto_fan_low == args.auto_fan_high {
+            return Err(LightsOutError::Config(anyhow!(
+                "--auto-fan-low and --auto-fan-high are both {:?}",
+                args.auto_fan_low
+            ))
+            .into());
+        }
+        Ok(Some(HysteresisController {
+            up_threshold: temp + args.fan_hysteresis,
+            down_threshold: temp - args.fan_hysteresis,
+            low_mode: args.auto_fan_low,
+            high_mode: args.auto_fan_high,
+            current_mode: args.auto_fan_low,
+            pending_up: None,
+            pending_down: None,
+            pending_readings: 0,
+            readings_required: args.fan_hysteresis_readings,
+        }))
+    }
+
+    /// Feed one reading; returns the mode to switch to once the condition has
+    /// held, and for how long
+    fn update(&mut self, temp: i32) -> Option<(FanMode, Duration)> {
+        let (wants_switch, pending, other, target) = if self.current_mode == self.high_mode {
+            (
+                temp <= self.down_threshold,
+                &mut self.pending_down,
+                &mut self.pending_up,
+                self.low_mode,
+            )
+        } else {
+            (
+                temp >= self.up_threshold,
+                &mut self.pending_up,
+                &mut self.pending_down,
+                self.high_mode,
+            )
+        };
+        *other = None;
+        if !wants_switch {
+            *pending = None;
+            self.pending_readings = 0;
+            return None;
+        }
+        pending.get_or_insert_with(Instant::now);
+        self.pending_readings += 1;
+        if self.pending_readings < self.readings_required {
+            return None;
+        }
+
+        let held = pending
+            .take()
+            .map_or(Duration::ZERO, |since| since.elapsed());
+        self.pending_readings = 0;
+        self.current_mode = target;
+        Some((target, held))
+    }
+}
+
+/// Where the daemon gets the CPU temperature from
+enum TempSource {
+    Sensor(std::path::PathBuf),
+    /// Simulated pattern and the time it started
+    Simulated(TempSimulation, Instant),
+}
+
+impl TempSource {
+    fn read(&self) -> Result<i32> {
+        match self {
+            TempSource::Sensor(path) => read_cpu_temp(path),
+            TempSource::Simulated(simulation, started) => Ok(simulation.temp_at(started.elapsed())),
+        }
+    }
+}
+
+/// Temperature curve for `msi temp-simulate`
+#[derive(Debug, Clone, Copy)]
+enum TempPattern {
+    /// Smooth swing from --min up to --max and back once per --period
+    Sine,
+    /// Linear climb from --min to --max each --period, then a drop back
+    Sawtooth,
+    /// Constant temperature
+    Step(i32),
+    /// Linear move from start to end over the duration, then held at end
+    Ramp(i32, i32, Duration),
+}
+
+impl TempPattern {
+    /// Pattern description with its temperatures shown in `unit`
+    fn describe(self, unit: TempUnit) -> String {
+        match self {
+            TempPattern::Sine => "sine".to_string(),
+            TempPattern::Sawtooth => "sawtooth".to_string(),
+            TempPattern::Step(temp) => format!("step {}", unit.show(temp)),
+            TempPattern::Ramp(start, end, duration) => format!(
+                "ramp {} to {} over {:?}",
+                unit.show(start),
+                unit.show(end),
+                duration
+            ),
+        }
+    }
+}
+
+/// Validated `msi temp-simulate` settings
+struct TempSimulation {
+    pattern: TempPattern,
+    period: Duration,
+    min: i32,
+    max: i32,
+}
+
+impl TempSimulation {
+    fn from_args(args: &TempSimulateArgs) -> Result<TempSimulation> {
+        let config_error =
+            |msg: String| -> anyhow::Error { LightsOutError::Config(anyhow!(msg)).into() };
+        let temp = |s: &str| {
+            s.parse::<i32>()
+                .map_err(|_| config_error(format!("Invalid temperature {:?}", s)))
+        };
+        let pattern = match args.pattern.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+            ["sine"] => TempPattern::Sine,
+            ["sawtooth"] => TempPattern::Sawtooth,
+            ["step", value] => TempPattern::Step(temp(value)?),
+            ["ramp", start, end, duration] => TempPattern::Ramp(
+                temp(start)?,
+                temp(end)?,
+                parse_duration(duration).map_err(LightsOutError::Config)?,
+            ),
+            _ => {
+                return Err(config_error(format!(
+                    "Invalid --pattern {:?} (expected sine, sawtooth, step <TEMP> or \
+                     ramp <START> <END> <DURATION>)",
+                    args.pattern.join(" ")
+                )))
+            }
+        };
+        if matches!(pattern, TempPattern::Sine | TempPattern::Sawtooth) {
+            if args.min >= args.max {
+                return Err(config_error(format!(
+                    "--min ({}) must be below --max ({})",
+                    args.min, args.max
+                )));
+            }
+            if args.period.is_zero() {
+                return Err(config_error(
+                    "--period must be longer than zero".to_string(),
+                ));
+            }
+        }
+        Ok(TempSimulation {
+            pattern,
+            period: args.period,
+            min: args.min,
+            max: args.max,
+        })
+    }
+
+    /// Simulated temperature after `elapsed` (rounded to whole degrees)
+    fn temp_at(&self, elapsed: Duration) -> i32 {
+        let span = (self.max - self.min) as f64;
+        let phase = || (elapsed.as_secs_f64() / self.period.as_secs_f64()).fract();
+        let temp = match self.pattern {
+            TempPattern::Sine => {
+                self.min as f64 + span * (0.5 - 0.5 * (std::f64::consts::TAU * phase()).cos())
+            }
+            TempPattern::Sawtooth => self.min as f64 + span * phase(),
+            TempPattern::Step(temp) => temp as f64,
+            TempPattern::Ramp(start, end, duration) => {
+                let progress = if duration.is_zero() {
+                    1.0
+                } else {
+                    (elapsed.as_secs_f64() / duration.as_secs_f64()).min(1.0)
+                };
+                start as f64 + (end - start) as f64 * progress
+            }
+        };
+        temp.round() as i32
+    }
+}
+
+/// Integer moving average over the last `window` values (at most N)
+struct MovingAverage<const N: usize> {
+    samples: [i32; N],
+    window: usize,
+    next: usize,
+    filled: usize,
+    sum: i64,
+}
+
+impl<const N: usize> MovingAverage<N> {
+    fn new(window: usize) -> MovingAverage<N> {
+        assert!((1..=N).contains(&window), "window must be 1-{}", N);
+        MovingAverage {
+            samples: [0; N],
+            window,
+            next: 0,
+            filled: 0,
+            sum: 0,
+        }
+    }
+
+    /// Add a value and return the mean of the buffered values, rounded to nearest
+    fn push(&mut self, val: i32) -> i32 {
+        if self.filled == self.window {
+            self.sum -= self.samples[self.next] as i64;
+        } else {
+            self.filled += 1;
+        }
+        self.samples[self.next] = val;
+        self.sum += val as i64;
+        self.next = (self.next + 1) % self.window;
+        let n = self.filled as i64;
+        (2 * self.sum + n).div_euclid(2 * n) as i32
+    }
+}
+
+/// Temperature statistics for one --stats-interval period
+struct StatsSummary {
+    /// When the daemon started, for the uptime
+    started: Instant,
+    period_start: Instant,
+    min: i32,
+    max: i32,
+    sum: i64,
+    count: u64,
+    fail_count: u64,
+    /// Failed reads since the daemon started (not reset per period)
+    total_failures: u64,
+}
+
+impl StatsSummary {
+    fn new() -> StatsSummary {
+        let now = Instant::now();
+        StatsSummary {
+            started: now,
+            period_start: now,
+            min: i32::MAX,
+            max: i32::MIN,
+            sum: 0,
+            count: 0,
+            fail_count: 0,
+            total_failures: 0,
+        }
+    }
+
+    fn record(&mut self, temp: i32) {
+        self.min = self.min.min(temp);
+        self.max = self.max.max(temp);
+        self.sum += temp as i64;
+        self.count += 1;
+    }
+
+    fn record_failure(&mut self) {
+        self.fail_count += 1;
+        self.total_failures += 1;
+    }
+
+    fn report(&self, unit: TempUnit) {
+        let period = self.period_start.elapsed().as_secs();
+        let uptime = self.started.elapsed().as_secs();
+        let uptime = format!(
+            "{}h{:02}m{:02}s",
+            uptime / 3600,
+            uptime / 60 % 60,
+            uptime % 60
+        );
+        if self.count == 0 {
+            println!(
+                "  Stats ({}s): no readings, {} failed read(s), uptime {}",
+                period, self.fail_count, uptime
+            );
+        } else {
+            println!(
+                "  Stats ({}s): min {}, max {}, avg {} over {} reading(s), \
+                 {} failed read(s), uptime {}",
+                period,
+                unit.show(self.min),
+                unit.show(self.max),
+                unit.show_f64(self.sum as f64 / self.count as f64),
+                self.count,
+                self.fail_count,
+                uptime
+            );
+        }
+    }
+
+    /// Start a new period, keeping the daemon start time
+    fn reset(&mut self) {
+        *self = StatsSummary {
+            started: self.started,
+            total_failures: self.total_failures,
+            ..StatsSummary::new()
+        };
+    }
+}
+
+/// Overheat alert: fast flashing while hot, previous LED report restored once cooled down
+struct OverheatAlert {
+    threshold: i32,
+    color: Rgb,
+    notify: bool,
+    unit: TempUnit,
+    /// LED reports from before the alert started, one per cooler; Some while
+    /// the alert is active
+    saved_reports: Option<Vec<[u8; msi::MAX_DATA_LEN]>>,
+}
+
+impl OverheatAlert {
+    fn new(args: &DaemonArgs) -> Option<OverheatAlert> {
+        args.alert_on_overheat.map(|threshold| OverheatAlert {
+            threshold,
+            color: args.alert_color,
+            notify: args.alert_notify,
+            unit: TempUnit::from_args(args),
+            saved_reports: None,
+        })
+    }
+
+    fn is_active(&self) -> bool {
+        self.saved_reports.is_some()
+    }
+
+    /// Start or stop the alert for the current temperature
+    ///
+    /// Returns true if the alert just ended, so effects can redraw.
+    fn update(&mut self, devices: &[HidDevice], temp: i32) -> Result<bool> {
+        if !self.is_active() && temp >= self.threshold {
+            let mut originals = Vec::with_capacity(devices.len());
+            for device in devices {
+                let original = msi_read_led_report(device)?;
+                let mut buf = original;
+                msi_fill_zones(&mut buf, msi::LED_MODE_FLASHING, self.color);
+                for &offset in msi::LED_OFFSETS {
+                    let flags = &mut buf[offset + msi::ZONE_SPEED_BRIGHTNESS];
+                    *flags = (*flags & !msi::LED_SPEED_MASK) | msi::LED_SPEED_HIGH;
+                }
+                msi_write_led_report(device, &buf)?;
+                originals.push(original);
+            }
+            self.saved_reports = Some(originals);
+            println!(
+                "  ALERT: CPU at {} (threshold {})",
+                self.unit.show(temp),
+                self.unit.show(self.threshold)
+            );
+            if self.notify {
+                notify_overheat(&self.unit.show(temp));
+            }
+        } else if self.is_active() && temp < self.threshold - msi::ALERT_HYSTERESIS_C {
+            self.restore(devices)?;
+            println!("  Alert cleared: CPU at {}", self.unit.show(temp));
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Put back the LED reports from before the alert, if one is active
+    fn restore(&mut self, devices: &[HidDevice]) -> Result<()> {
+        for (device, report) in devices
+            .iter()
+            .zip(self.saved_reports.take().unwrap_or_default())
+        {
+            msi_write_led_report(device, &report)?;
+        }
+        Ok(())
+    }
+}
+
+/// Desktop notification via notify-send; failures are only warned about
+fn notify_overheat(temp: &str) {
+    let result = std::process::Command::new("notify-send")
+        .args(["--urgency=critical", "CPU overheating"])
+        .arg(format!("CPU temperature is {}", temp))
+        .status();
+    if let Err(e) = result {
+        eprintln!("  Warning: Failed to run notify-send: {}", e);
+    }
+}
+
+/// Compute the effect color for this cycle and write it to all MSI zones if it changed
+fn apply_daemon_effect(
+    devices: &[HidDevice],
+    args: &DaemonArgs,
+    effect: DaemonEffect,
+    temp: Option<i32>,
+    last_color: &mut Option<Rgb>,
+    wallpaper: &mut WallpaperWatcher,
+) -> Result<()> {
+    let rgb = match effect {
+        DaemonEffect::OverclockIndicator => {
+            let freq = read_max_cpu_freq_mhz()?;
+            let span = args.boost_freq.saturating_sub(args.base_freq).max(1);
+            args.effect_color(freq.saturating_sub(args.base_freq) as f32 / span as f32)
+        }
+        DaemonEffect::CpuReactive => {
+            // Nothing to show until the first successful reading
+            let Some(temp) = temp else { return Ok(()) };
+            let span = (args.hot_temp - args.cold_temp).max(1);
+            args.effect_color((temp - args.cold_temp) as f32 / span as f32)
+        }
+        DaemonEffect::WallpaperReactive => match wallpaper.poll()? {
+            Some(rgb) => rgb,
+            None => return Ok(()),
+        },
+    };
+
+    if *last_color != Some(rgb) {
+        for device in devices {
+            msi_set_all_leds(device, msi::LED_MODE_STATIC, rgb)?;
+        }
+        if let DaemonEffect::WallpaperReactive = effect {
+            set_other_devices_color(rgb);
+        }
+        *last_color = Some(rgb);
+    }
+    Ok(())
+}
+
+/// Best-effort static color on the LianLi hub and GPU (the daemon only owns the MSI cooler)
+fn set_other_devices_color(rgb: Rgb) {
+    let lianli = (|| -> Result<()> {
+        let config = Config::load()?;
+        let mut state = LianliState::load()?;
+        let api = HidApi::new().context("Failed to initialize HID API")?;
+        let device = lianli_open(&api)?;
+        lianli_set_color(&device, &config, &mut state, rgb)?;
+        state.save()
+    })();
+    if let Err(e) = lianli {
+        eprintln!("  Warning: LianLi UNI FAN: {:#}", e);
+    }
+
+    let gpu = find_gpu_i2c_bus()
+        .and_then(|bus| gpu_open_bus(&bus))
+        .and_then(|mut device| ene_set_mode(&mut device, GpuLedMode::Static, Some(rgb)));
+    if let Err(e) = gpu {
+        eprintln!("  Warning: GPU: {:#}", e);
+    }
+}
+
+/// Highest current frequency across all CPU cores in MHz
+fn read_max_cpu_freq_mhz() -> Result<u32> {
+    let cpu_path = Path::new("/sys/devices/system/cpu");
+    let mut max_khz = None;
+
+    for entry in fs::read_dir(cpu_path).context("Failed to read /sys/devices/system/cpu")? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        // Only cpuN directories, not cpufreq/cpuidle
+        if !name
+            .strip_prefix("cpu")
+            .is_some_and(|n| n.chars().all(|c| c.is_ascii_digit()) && !n.is_empty())
+        {
+            continue;
+        }
+        let freq_path = entry.path().join("cpufreq/scaling_cur_freq");
+        if let Ok(content) = fs::read_to_string(&freq_path) {
+            if let Ok(khz) = content.trim().parse::<u32>() {
+                max_khz = max_khz.max(Some(khz));
+            }
+        }
+    }
+
+    let max_khz = max_khz.ok_or_else(|| {
+        LightsOutError::DeviceNotFound(anyhow!("No cpufreq scaling_cur_freq files found"))
+    })?;
+    Ok(max_khz / 1000)
+}
+
+fn lianli_open(api: &HidApi) -> Result<HidDevice> {
+    open_hid_device(api, lianli::VID, lianli::PID, "LianLi UNI FAN AL V2")
+}
+
+/// What the hub reports about itself, plus the protocol's fixed limits
+#[derive(Debug, Serialize)]
+struct LianliHubInfo {
+    product: Option<String>,
+    /// Version suffix of the product string, if it has one
+    firmware: Option<String>,
+    /// USB release number (bcdDevice)
+    usb_release: String,
+    channels: u8,
+    max_fans_per_channel: u8,
+    fan_leds_per_fan: usize,
+    edge_leds_per_fan: usize,
+}
+
+impl LianliHubInfo {
+    /// Read the firmware version from the hub's USB descriptor
+    ///
+    /// No documented command returns the version, and the hub doesn't report its
+    /// channel or LED counts either, so those are the protocol limits this tool
+    /// drives.
+    fn query(device: &HidDevice) -> Result<LianliHubInfo> {
+        let info = device
+            .get_device_info()
+            .context("Failed to read the hub's USB descriptor")?;
+        let product = info.product_string().map(str::to_string);
+
+        Ok(LianliHubInfo {
+            firmware: product.as_deref().and_then(lianli_product_version),
+            product,
+            usb_release: usb_release_version(info.release_number()),
+            channels: lianli::NUM_CHANNELS,
+            max_fans_per_channel: lianli::MAX_FANS_PER_CHANNEL,
+            fan_leds_per_fan: lianli::FAN_LEDS_PER_FAN,
+            edge_leds_per_fan: lianli::EDGE_LEDS_PER_FAN,
+        })
+    }
+}
+
+/// Version suffix of a product string like "LianLi-UNI FAN-AL-v1.7"
+fn lianli_product_version(product: &str) -> Option<String> {
+    product
+        .rsplit_once("-v")
+        .map(|(_, version)| version)
+        .filter(|version| version.starts_with(|c: char| c.is_ascii_digit()))
+        .map(str::to_string)
+}
+
+/// Channels to drive and the number of fans on each, from the port map in the config
+///
+/// Without a port map every channel is assumed to be fully populated.
+fn lianli_channels(config: &Config) -> Vec<(u8, u8)> {
+    if config.lianli.channels.is_empty() {
+        return (0..lianli::NUM_CHANNELS)
+            .map(|channel| (channel, lianli::MAX_FANS_PER_CHANNEL))
+            .collect();
+    }
+    config
+        .lianli
+        .channels
+        .iter()
+        .filter(|(_, ch)| ch.fans > 0)
+        .map(|(&channel, ch)| (channel, ch.fans))
+        .collect()
+}
+
+/// Number of fans on one channel, or an error if the port map leaves it empty
+fn lianli_channel_fans(config: &Config, channel: u8) -> Result<u8> {
+    let fans = lianli_channels(config)
+        .into_iter()
+        .find(|&(ch, _)| ch == channel)
+        .map(|(_, fans)| fans)
+        .ok_or_else(|| {
+            LightsOutError::Config(anyhow!("No fans configured on channel {}", channel))
+        })?;
+    Ok(fans)
+}
+
+/// Send uniform fan and edge colors to one channel
+///
+/// Colors are sent in R, B, G order as the hub expects. Like the other color
+/// packets, failed writes are only warned about; the commit that follows is
+/// what reports errors.
+fn lianli_send_colors(device: &HidDevice, channel: u8, fans: u8, fan_color: Rgb, edge_color: Rgb) {
+    for color_packet in lianli_uniform_color_packets(channel, fans, fan_color, edge_color) {
+        lianli_write_color_packet(device, &color_packet);
+    }
+}
+
+/// Fan and edge ring color packets with every LED of a ring in one color
+fn lianli_uniform_color_packets(
+    channel: u8,
+    fans: u8,
+    fan_color: Rgb,
+    edge_color: Rgb,
+) -> [Vec<u8>; 2] {
+    [
+        (lianli::REG_FAN_COLOR, lianli::FAN_LEDS_PER_FAN, fan_color),
+        (
+            lianli::REG_EDGE_COLOR,
+            lianli::EDGE_LEDS_PER_FAN,
+            edge_color,
+        ),
+    ]
+    .map(|(reg, leds_per_fan, rgb)| {
+        let mut color_packet = lianli_color_packet(channel, reg, fans, leds_per_fan);
+        for led in 0..fans as usize * leds_per_fan {
+            lianli_set_led(&mut color_packet, led, rgb);
+        }
+        color_packet
+    })
+}
+
+/// Empty (all black) color packet for one ring of a channel
+fn lianli_color_packet(channel: u8, reg: u8, fans: u8, leds_per_fan: usize) -> Vec<u8> {
+    let mut packet = vec![0u8; lianli::color_packet_len(fans, leds_per_fan)];
+    packet[0] = lianli::TRANSACTION_ID;
+    packet[1] = reg + (channel * 2);
+    packet
+}
+
+/// Set one LED in a color packet
+///
+/// LEDs follow the 2-byte header as R, B, G triples, fan by fan.
+fn lianli_set_led(packet: &mut [u8], led_index: usize, rgb: Rgb) {
+    let start = 2 + led_index * 3;
+    packet[start..start + 3].copy_from_slice(&[rgb.r, rgb.b, rgb.g]);
+}
+
+/// Send a color packet; failures are only warned about so the other rings still update
+fn lianli_write_color_packet(device: &HidDevice, packet: &[u8]) {
+    if let Err(e) = device.write(packet) {
+        eprintln!(
+            "    Warning: color packet reg 0x{:02x} failed: {}",
+            packet[1], e
+        );
+    }
+    std::thread::sleep(Duration::from_millis(lianli::PACKET_DELAY_MS));
+}
+
+/// Send the commit action (mode, speed, direction, brightness) for fan and edge LEDs
+fn lianli_commit(device: &HidDevice, channel: u8, state: &LianliChannelState) -> Result<()> {
+    let [fan_commit, edge_commit] = lianli_commit_packets(channel, state);
+    device
+        .write(&fan_commit)
+        .context("Failed to write fan LED commit")?;
+    std::thread::sleep(Duration::from_millis(lianli::PACKET_DELAY_MS));
+
+    device
+        .write(&edge_commit)
+        .context("Failed to write edge LED commit")?;
+    std::thread::sleep(Duration::from_millis(lianli::PACKET_DELAY_MS));
+    Ok(())
+}
+
+/// Fan and edge commit packets (65 bytes each) for one channel
+fn lianli_commit_packets(
+    channel: u8,
+    state: &LianliChannelState,
+) -> [[u8; lianli::PACKET_SIZE]; 2] {
+    [lianli::REG_FAN_COMMIT, lianli::REG_EDGE_COMMIT].map(|reg| {
+        let mut commit = [0u8; lianli::PACKET_SIZE];
+        commit[0] = lianli::TRANSACTION_ID;
+        commit[1] = reg + (channel * 2);
+        commit[2] = state.mode;
+        commit[3] = state.speed;
+        commit[4] = state.direction;
+        commit[5] = state.brightness;
+        commit
+    })
+}
+
+/// Show the packets for the cached state; the hub can't be read back, so
+/// these are computed locally
+fn lianli_hex_dump(kind: LianliPacketType, channel: Option<u8>) -> Result<()> {
+    let config = Config::load()?;
+    let state = LianliState::load()?;
+    let channels = match channel {
+        Some(channel) => vec![(channel, lianli_channel_fans(&config, channel)?)],
+        None => lianli_channels(&config),
+    };
+
+    for (channel, fans) in channels {
+        let ch_state = lianli_channel_state(&state, &config, channel);
+        let packets: Vec<Vec<u8>> = match kind {
+            LianliPacketType::Color => {
+                lianli_uniform_color_packets(channel, fans, ch_state.fan_color, ch_state.edge_color)
+                    .into()
+            }
+            LianliPacketType::Commit => lianli_commit_packets(channel, &ch_state)
+                .iter()
+                .map(|packet| packet.to_vec())
+                .collect(),
+        };
+        for (ring, packet) in ["fan", "edge"].iter().zip(packets) {
+            println!(
+                "Channel {} {} {} packet ({} bytes):",
+                channel,
+                ring,
+                kind.to_possible_value()
+                    .map_or_else(String::new, |v| v.get_name().to_string()),
+                packet.len()
+            );
+            print_hex_dump(&packet);
+        }
+    }
+    Ok(())
+}
+
+/// Configured speed for a channel, if any
+fn lianli_config_speed(config: &Config, channel: u8) -> Option<LianliSpeed> {
+    config.lianli.channels.get(&channel).and_then(|ch| ch.speed)
+}
+
+/// Cached state of a channel; one that was never written starts at its configured speed
+fn lianli_channel_state(state: &LianliState, config: &Config, channel: u8) -> LianliChannelState {
+    let mut ch_state = state.channel(channel);
+    if !state.channels.contains_key(&channel) {
+        if let Some(speed) = lianli_config_speed(config, channel) {
+            ch_state.speed = speed as u8;
+        }
+    }
+    ch_state
+}
+
+fn lianli_disable() -> Result<()> {
+    let config = Config::load()?;
+    let mut state = LianliState::load()?;
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+
+    let device = lianli_open(&api)?;
+
+    // Following OpenRGB LianLiUniHubALController protocol:
+    // 1. Send color data (all black) - sized for the fans on the channel
+    // 2. Send commit action with 0% brightness - 65 byte packet
+
+    for (channel, fans) in lianli_channels(&config) {
+        lianli_send_colors(&device, channel, fans, Rgb::BLACK, Rgb::BLACK);
+
+        let ch_state = LianliChannelState {
+            mode: lianli::MODE_STATIC,
+            speed: lianli_config_speed(&config, channel)
+                .map_or(lianli::SPEED_VERY_SLOW, |speed| speed as u8),
+            direction: lianli::DIRECTION_LEFT_TO_RIGHT,
+            brightness: lianli::BRIGHTNESS_OFF,
+            fan_color: Rgb::BLACK,
+            edge_color: Rgb::BLACK,
+        };
+        lianli_commit(&device, channel, &ch_state)?;
+        state.channels.insert(channel, ch_state);
+    }
+    state.save()?;
+
+    println!("  LianLi UNI FAN AL V2: LEDs disabled (static black, 0% brightness)");
+    Ok(())
+}
+
+/// Set fan and edge LEDs of all configured channels to static colors at full
+/// brightness and cache them
+fn lianli_set_zones(
+    device: &HidDevice,
+    config: &Config,
+    state: &mut LianliState,
+    fan_color: Rgb,
+    edge_color: Rgb,
+) -> Result<()> {
+    for (channel, fans) in lianli_channels(config) {
+        lianli_set_channel_zones(device, state, channel, fans, fan_color, edge_color)?;
+    }
+    Ok(())
+}
+
+/// Set fan and edge LEDs of one channel to static colors at full brightness and cache them
+fn lianli_set_channel_zones(
+    device: &HidDevice,
+    state: &mut LianliState,
+    channel: u8,
+    fans: u8,
+    fan_color: Rgb,
+    edge_color: Rgb,
+) -> Result<()> {
+    lianli_send_colors(device, channel, fans, fan_color, edge_color);
+    let ch_state = LianliChannelState {
+        mode: lianli::MODE_STATIC,
+        brightness: lianli::BRIGHTNESS_FULL,
+        fan_color,
+        edge_color,
+        ..state.channel(channel)
+    };
+    lianli_commit(device, channel, &ch_state)?;
+    state.channels.insert(channel, ch_state);
+    Ok(())
+}
+
+/// Spread a gradient across the fans of all configured channels, in channel order
+///
+/// The state cache only holds one color per ring, so each channel's middle
+/// color is recorded there.
+fn lianli_apply_gradient(
+    device: &HidDevice,
+    config: &Config,
+    state: &mut LianliState,
+    stops: &[(f32, Rgb)],
+) -> Result<()> {
+    let channels = lianli_channels(config);
+    let total_fans: usize = channels.iter().map(|&(_, fans)| fans as usize).sum();
+    let mut first_fan = 0;
+    for &(channel, fans) in &channels {
+        let mut middle = [Rgb::BLACK; 2];
+        for (ring, (reg, leds_per_fan)) in [
+            (lianli::REG_FAN_COLOR, lianli::FAN_LEDS_PER_FAN),
+            (lianli::REG_EDGE_COLOR, lianli::EDGE_LEDS_PER_FAN),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let total_leds = (total_fans * leds_per_fan).max(2) - 1;
+            let offset = first_fan * leds_per_fan;
+            let color_at =
+                |led: usize| gradient_at(stops, (offset + led) as f32 / total_leds as f32);
+            let mut packet = lianli_color_packet(channel, reg, fans, leds_per_fan);
+            let count = fans as usize * leds_per_fan;
+            for led in 0..count {
+                lianli_set_led(&mut packet, led, color_at(led));
+            }
+            lianli_write_color_packet(device, &packet);
+            middle[ring] = color_at(count / 2);
+        }
+        let ch_state = LianliChannelState {
+            mode: lianli::MODE_STATIC,
+            brightness: lianli::BRIGHTNESS_FULL,
+            fan_color: middle[0],
+            edge_color: middle[1],
+            ..state.channel(channel)
+        };
+        lianli_commit(device, channel, &ch_state)?;
+        state.channels.insert(channel, ch_state);
+        first_fan += fans as usize;
+    }
+    Ok(())
+}
+
+fn lianli_color_scene(scene: ColorScene) -> Result<()> {
+    let config = Config::load()?;
+    let mut state = LianliState::load()?;
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = lianli_open(&api)?;
+
+    lianli_apply_gradient(&device, &config, &mut state, scene.stops())?;
+    state.save()?;
+
+    println!("  LianLi UNI FAN AL V2: Applied {:?} scene", scene);
+    Ok(())
+}
+
+fn lianli_color_from_image(path: &Path, zones: Option<u8>) -> Result<()> {
+    let config = Config::load()?;
+    let channels = lianli_channels(&config);
+    let zones = zones.map_or(channels.len(), |zones| zones as usize);
+    if zones > channels.len() {
+        return Err(LightsOutError::Config(anyhow!(
+            "--zones {} is more than the {} configured channel(s)",
+            zones,
+            channels.len()
+        ))
+        .into());
+    }
+    let img = image::open(path)
+        .with_context(|| format!("Failed to load {}", path.display()))
+        .map_err(LightsOutError::Config)?;
+    let colors = extract_zone_colors(&img, zones);
+
+    let mut state = LianliState::load()?;
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = lianli_open(&api)?;
+    for (&(channel, fans), &rgb) in channels.iter().zip(&colors) {
+        lianli_set_channel_zones(&device, &mut state, channel, fans, rgb, rgb)?;
+        println!("  LianLi UNI FAN AL V2: Channel {} set to {}", channel, rgb);
+    }
+    state.save()?;
+    Ok(())
+}
+
+/// Set one channel to a hue on the HSV color wheel
+fn lianli_color_wheel(channel: u8, position: u16, saturation: u8, value: u8) -> Result<()> {
+    let config = Config::load()?;
+    let fans = lianli_channel_fans(&config, channel)?;
+    let rgb = hsv_to_rgb(
+        position as f32,
+        saturation as f32 / 100.0,
+        value as f32 / 100.0,
+    );
+
+    let mut state = LianliState::load()?;
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = lianli_open(&api)?;
+    lianli_set_channel_zones(&device, &mut state, channel, fans, rgb, rgb)?;
+    state.save()?;
+
+    println!(
+        "  LianLi UNI FAN AL V2: Channel {} set to {}° ({})",
+        channel, position, rgb
+    );
+    Ok(())
+}
+
+/// Set all configured channels to one static color
+fn lianli_set_color(
+    device: &HidDevice,
+    config: &Config,
+    state: &mut LianliState,
+    rgb: Rgb,
+) -> Result<()> {
+    lianli_set_zones(device, config, state, rgb, rgb)
+}
+
+fn lianli_color_zones(fan_color: Rgb, edge_color: Rgb) -> Result<()> {
+    let config = Config::load()?;
+    let mut state = LianliState::load()?;
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = lianli_open(&api)?;
+
+    lianli_set_zones(&device, &config, &mut state, fan_color, edge_color)?;
+    state.save()?;
+
+    println!(
+        "  LianLi UNI FAN AL V2: Fan LEDs {}, edge LEDs {}",
+        fan_color, edge_color
+    );
+    Ok(())
+}
+
+/// One step of a LianLi effect chain
+#[derive(Debug, Clone, PartialEq)]
+enum ChainStep {
+    Static {
+        color: Rgb,
+        duration_ms: u64,
+    },
+    Fade {
+        from: Rgb,
+        to: Rgb,
+        duration_ms: u64,
+    },
+    Rainbow {
+        duration_ms: u64,
+    },
+}
+
+struct EffectChain {
+    steps: Vec<ChainStep>,
+}
+
+impl EffectChain {
+    /// Parse "static:red:5s,fade:blue:2s,rainbow:3s"
+    ///
+    /// A fade starts from the color the previous step ended on (black for the first step).
+    fn parse(spec: &str) -> Result<Vec<ChainStep>> {
+        let mut steps = Vec::new();
+        let mut current = Rgb::BLACK;
+        for step in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let parts: Vec<&str> = step.split(':').collect();
+            let duration_ms =
+                |s: &str| -> Result<u64> { Ok(parse_duration(s)?.as_millis() as u64) };
+            let parsed = match parts.as_slice() {
+                ["static", color, time] => {
+                    current = color.parse()?;
+                    ChainStep::Static {
+                        color: current,
+                        duration_ms: duration_ms(time)?,
+                    }
+                }
+                ["fade", color, time] => {
+                    let from = current;
+                    current = color.parse()?;
+                    ChainStep::Fade {
+                        from,
+                        to: current,
+                        duration_ms: duration_ms(time)?,
+                    }
+                }
+                ["rainbow", time] => ChainStep::Rainbow {
+                    duration_ms: duration_ms(time)?,
+                },
+                _ => anyhow::bail!(
+                    "Invalid effect chain step {:?} (expected static:<color>:<time>, \
+                     fade:<color>:<time> or rainbow:<time>)",
+                    step
+                ),
+            };
+            steps.push(parsed);
+        }
+        if steps.is_empty() {
+            anyhow::bail!("Effect chain is empty");
+        }
+        Ok(steps)
+    }
+
+    /// Play the steps in order, looping until the stop flag is set
+    fn run(
+        &self,
+        device: &HidDevice,
+        config: &Config,
+        state: &mut LianliState,
+        stop_flag: &AtomicBool,
+    ) -> Result<()> {
+        while !stop_flag.load(Ordering::Relaxed) {
+            for step in &self.steps {
+                if stop_flag.load(Ordering::Relaxed) {
+                    break;
+                }
+                match *step {
+                    ChainStep::Static { color, duration_ms } => {
+                        println!("  Static {}", color);
+                        lianli_set_color(device, config, state, color)?;
+                        sleep_unless_stopped(stop_flag, Duration::from_millis(duration_ms));
+                    }
+                    ChainStep::Fade {
+                        from,
+                        to,
+                        duration_ms,
+                    } => {
+                        println!("  Fade {} -> {}", from, to);
+                        // Frame rate is bounded by the 20 ms inter-packet delay
+                        let start = Instant::now();
+                        let duration = Duration::from_millis(duration_ms);
+                        while !stop_flag.load(Ordering::Relaxed) && start.elapsed() < duration {
+                            let t = start.elapsed().as_secs_f32() / duration.as_secs_f32();
+                            lianli_set_color(device, config, state, lerp_color(from, to, t))?;
+                        }
+                        lianli_set_color(device, config, state, to)?;
+                    }
+                    ChainStep::Rainbow { duration_ms } => {
+                        println!("  Rainbow");
+                        for (channel, _) in lianli_channels(config) {
+                            let ch_state = LianliChannelState {
+                                mode: lianli::MODE_RAINBOW,
+                                brightness: lianli::BRIGHTNESS_FULL,
+                                ..lianli_channel_state(state, config, channel)
+                            };
+                            lianli_commit(device, channel, &ch_state)?;
+                            state.channels.insert(channel, ch_state);
+                        }
+                        sleep_unless_stopped(stop_flag, Duration::from_millis(duration_ms));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn lianli_effect_chain(spec: &str) -> Result<()> {
+    let chain = EffectChain {
+        steps: EffectChain::parse(spec).map_err(LightsOutError::Config)?,
+    };
+    let config = Config::load()?;
+    let mut state = LianliState::load()?;
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = lianli_open(&api)?;
+
+    let stop_flag = install_stop_handler()?;
+    println!("  Running effect chain (Ctrl+C to stop)...");
+    let result = chain.run(&device, &config, &mut state, &stop_flag);
+    // Record whatever was applied last, even if the chain failed midway
+    state.save()?;
+    result
+}
+
+fn lianli_save_state(output: Option<&Path>) -> Result<()> {
+    let state = LianliState::load()?;
+    let path = output.map_or_else(LianliState::path, Path::to_path_buf);
+    state.save_to(&path)?;
+    println!(
+        "Saved state for {} channel(s) to {}",
+        state.channels.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+fn lianli_load_state(input: Option<&Path>) -> Result<()> {
+    let path = input.map_or_else(LianliState::path, Path::to_path_buf);
+    let state = LianliState::load_from(&path).map_err(LightsOutError::Config)?;
+    if state.channels.is_empty() {
+        return Err(
+            LightsOutError::Config(anyhow!("No LianLi state saved in {}", path.display())).into(),
+        );
+    }
+
+    let config = Config::load()?;
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = lianli_open(&api)?;
+
+    for (channel, fans) in lianli_channels(&config) {
+        let Some(ch_state) = state.channels.get(&channel) else {
+            continue;
+        };
+        lianli_send_colors(
+            &device,
+            channel,
+            fans,
+            ch_state.fan_color,
+            ch_state.edge_color,
+        );
+        lianli_commit(&device, channel, ch_state)?;
+        println!(
+            "  Channel {}: mode 0x{:02x}, fan {}, edge {}",
+            channel, ch_state.mode, ch_state.fan_color, ch_state.edge_color
+        );
+    }
+    // What's on the hub now is what the state file says
+    if input.is_some() {
+        state.save()?;
+    }
+
+    println!(
+        "  LianLi UNI FAN AL V2: State restored from {}",
+        path.display()
+    );
+    Ok(())
+}
+
+fn lianli_set_speed(channel: Option<u8>, speed: LianliSpeed) -> Result<()> {
+    let config = Config::load()?;
+    let mut state = LianliState::load()?;
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = lianli_open(&api)?;
+
+    for (ch, _) in lianli_channels(&config)
+        .into_iter()
+        .filter(|&(ch, _)| channel.is_none_or(|c| c == ch))
+    {
+        // Only the speed byte changes; mode and brightness come from the cached state
+        let mut ch_state = state.channel(ch);
+        ch_state.speed = speed as u8;
+        lianli_commit(&device, ch, &ch_state)?;
+        state.channels.insert(ch, ch_state);
+    }
+    state.save()?;
+
+    println!("  LianLi UNI FAN AL V2: Speed set to {:?}", speed);
+    Ok(())
+}
+
+fn lianli_controller_info(json: bool) -> Result<()> {
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = lianli_open(&api)?;
+    let info = LianliHubInfo::query(&device)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&info)?);
+        return Ok(());
+    }
+    println!(
+        "  Product:              {}",
+        info.product.as_deref().unwrap_or("-")
+    );
+    println!(
+        "  Firmware:             {}",
+        info.firmware.as_deref().unwrap_or("unknown")
+    );
+    println!("  USB release:          {}", info.usb_release);
+    println!("  Channels:             {}", info.channels);
+    println!("  Fans per channel:     {}", info.max_fans_per_channel);
+    println!(
+        "  LEDs per fan:         {} fan ring, {} edge ring",
+        info.fan_leds_per_fan, info.edge_leds_per_fan
+    );
+    Ok(())
+}
+
+fn lianli_hub_passthrough(packets: &[String], padding: Padding) -> Result<()> {
+    let packets = packets
+        .iter()
+        .map(|packet| {
+            let mut data = parse_hex_bytes(std::slice::from_ref(packet))?;
+            if data.is_empty() {
+                anyhow::bail!("Empty packet");
+            }
+            if let Padding::Zeros = padding {
+                if data.len() > lianli::PACKET_SIZE {
+                    anyhow::bail!(
+                        "Packet is {} bytes, longer than {} (use --padding none)",
+                        data.len(),
+                        lianli::PACKET_SIZE
+                    );
+                }
+                data.resize(lianli::PACKET_SIZE, 0);
+            }
+            Ok(data)
+        })
+        .collect::<Result<Vec<_>>>()
+        .map_err(LightsOutError::Config)?;
+
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = lianli_open(&api)?;
+
+    for packet in &packets {
+        device.write(packet).context("Failed to write packet")?;
+        println!("Sent {} bytes:", packet.len());
+        print_hex_dump(packet);
+
+        let mut resp = [0u8; lianli::PACKET_SIZE];
+        let n = device
+            .read_timeout(&mut resp, lianli::READ_TIMEOUT_MS)
+            .context("Failed to read response")?;
+        if n > 0 {
+            println!("Received {} bytes:", n);
+            print_hex_dump(&resp[..n]);
+        }
+        std::thread::sleep(Duration::from_millis(lianli::PACKET_DELAY_MS));
+    }
+    Ok(())
+}
+
+/// Send frames read from stdin straight to the LEDs
+///
+/// The hub has no separate direct mode; static mode with per-LED color
+/// packets shows each frame as sent, without any animation in between.
+fn lianli_direct_mode(fps: u32) -> Result<()> {
+    let config = Config::load()?;
+    let channels = lianli_channels(&config);
+    let frame_len: usize = channels
+        .iter()
+        .map(|&(_, fans)| {
+            fans as usize * (lianli::FAN_LEDS_PER_FAN + lianli::EDGE_LEDS_PER_FAN) * 3
+        })
+        .sum();
+
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = lianli_open(&api)?;
+    let frame_interval = Duration::from_secs_f64(1.0 / fps as f64);
+    let static_state = LianliChannelState {
+        mode: lianli::MODE_STATIC,
+        brightness: lianli::BRIGHTNESS_FULL,
+        ..LianliChannelState::default()
+    };
+
+    eprintln!(
+        "  Reading {}-byte frames from stdin (at most {} fps)...",
+        frame_len, fps
+    );
+    for (line_no, line) in io::stdin().lines().enumerate() {
+        let line = line.context("Failed to read frame from stdin")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let started = Instant::now();
+        let frame = parse_hex_bytes(&[line]).map_err(LightsOutError::Config)?;
+        if frame.len() != frame_len {
+            return Err(LightsOutError::Config(anyhow!(
+                "Frame {} has {} bytes, expected {}",
+                line_no + 1,
+                frame.len(),
+                frame_len
+            ))
+            .into());
+        }
+
+        let mut rgbs = frame.chunks_exact(3).map(|c| Rgb::new(c[0], c[1], c[2]));
+        for &(channel, fans) in &channels {
+            for (reg, leds_per_fan) in [
+                (lianli::REG_FAN_COLOR, lianli::FAN_LEDS_PER_FAN),
+                (lianli::REG_EDGE_COLOR, lianli::EDGE_LEDS_PER_FAN),
+            ] {
+                let mut packet = lianli_color_packet(channel, reg, fans, leds_per_fan);
+                for (led, rgb) in (0..fans as usize * leds_per_fan).zip(&mut rgbs) {
+                    lianli_set_led(&mut packet, led, rgb);
+                }
+                lianli_write_color_packet(&device, &packet);
+            }
+            lianli_commit(&device, channel, &static_state)?;
+        }
+
+        std::thread::sleep(frame_interval.saturating_sub(started.elapsed()));
+    }
+    Ok(())
+}
+
+/// Light each LED of a channel in turn, fan ring first, then edge ring
+fn lianli_test_pattern(channel: Option<u8>, dwell: Option<Duration>) -> Result<()> {
+    let config = Config::load()?;
+    let state = LianliState::load()?;
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = lianli_open(&api)?;
+
+    let channels: Vec<(u8, u8)> = lianli_channels(&config)
+        .into_iter()
+        .filter(|&(ch, _)| channel.is_none_or(|c| c == ch))
+        .collect();
+    if channels.is_empty() {
+        return Err(
+            LightsOutError::Config(anyhow!("No fans configured on the selected channel")).into(),
+        );
+    }
+
+    // See msi_led_test: Ctrl+C is only caught in automatic mode
+    let stop_flag = match dwell {
+        Some(_) => install_stop_handler()?,
+        None => Arc::new(AtomicBool::new(false)),
+    };
+
+    let result = (|| -> Result<()> {
+        for &(ch, fans) in &channels {
+            let ch_state = LianliChannelState {
+                mode: lianli::MODE_STATIC,
+                brightness: lianli::BRIGHTNESS_FULL,
+                ..lianli_channel_state(&state, &config, ch)
+            };
+            for (reg, ring, leds_per_fan) in [
+                (lianli::REG_FAN_COLOR, "fan", lianli::FAN_LEDS_PER_FAN),
+                (lianli::REG_EDGE_COLOR, "edge", lianli::EDGE_LEDS_PER_FAN),
+            ] {
+                for led in 0..fans as usize * leds_per_fan {
+                    // Blank both rings, then light the one LED under test
+                    lianli_send_colors(&device, ch, fans, Rgb::BLACK, Rgb::BLACK);
+                    let mut packet = lianli_color_packet(ch, reg, fans, leds_per_fan);
+                    lianli_set_led(&mut packet, led, Rgb::WHITE);
+                    lianli_write_color_packet(&device, &packet);
+                    lianli_commit(&device, ch, &ch_state)?;
+
+                    let message = format!(
+                        "Channel {} {} LED {} (fan {}, LED {}) is now lit",
+                        ch,
+                        ring,
+                        led,
+                        led / leds_per_fan,
+                        led % leds_per_fan
+                    );
+                    if !wait_for_next_step(dwell, &stop_flag, &message)? {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        Ok(())
+    })();
+
+    // Put back what was last applied to each tested channel
+    for &(ch, fans) in &channels {
+        let ch_state = lianli_channel_state(&state, &config, ch);
+        lianli_send_colors(&device, ch, fans, ch_state.fan_color, ch_state.edge_color);
+        lianli_commit(&device, ch, &ch_state)?;
+    }
+    println!("  LianLi UNI FAN AL V2: Previous LED settings restored");
+    result
+}
+
+/// Software chase: step one lit LED around every ring until stopped, then restore the saved state
+fn lianli_color_animation(kind: LianliAnimation, speed: Option<u8>, rgb: Rgb) -> Result<()> {
+    let config = Config::load()?;
+    let state = LianliState::load()?;
+    let channels = lianli_channels(&config);
+
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = lianli_open(&api)?;
+    let stop_flag = install_stop_handler()?;
+
+    match speed {
+        Some(speed) => println!(
+            "  LianLi UNI FAN AL V2: Running {:?} at speed {} (Ctrl+C to stop)",
+            kind, speed
+        ),
+        None => println!(
+            "  LianLi UNI FAN AL V2: Running {:?} at the configured speeds (Ctrl+C to stop)",
+            kind
+        ),
+    }
+    // Without --speed each channel steps at its configured speed
+    let steps: Vec<u128> = channels
+        .iter()
+        .map(|&(channel, _)| {
+            let level = speed
+                .or_else(|| lianli_config_speed(&config, channel).map(LianliSpeed::chase_level))
+                .unwrap_or(lianli::CHASE_DEFAULT_LEVEL);
+            lianli::CHASE_STEP_MS[level as usize - 1] as u128
+        })
+        .collect();
+    let tick = Duration::from_millis(steps.iter().copied().min().unwrap_or(1) as u64);
+    let static_state = LianliChannelState {
+        mode: lianli::MODE_STATIC,
+        brightness: lianli::BRIGHTNESS_FULL,
+        ..LianliChannelState::default()
+    };
+    let result = (|| -> Result<()> {
+        let animation_start = Instant::now();
+        let mut positions: Vec<Option<usize>> = vec![None; channels.len()];
+        while !stop_flag.load(Ordering::Relaxed) {
+            let started = Instant::now();
+            for (i, &(channel, fans)) in channels.iter().enumerate() {
+                let position = (animation_start.elapsed().as_millis() / steps[i]) as usize;
+                if positions[i] == Some(position) {
+                    continue;
+                }
+                positions[i] = Some(position);
+                for (reg, leds_per_fan) in [
+                    (lianli::REG_FAN_COLOR, lianli::FAN_LEDS_PER_FAN),
+                    (lianli::REG_EDGE_COLOR, lianli::EDGE_LEDS_PER_FAN),
+                ] {
+                    let ring_len = fans as usize * leds_per_fan;
+                    let mut packet = lianli_color_packet(channel, reg, fans, leds_per_fan);
+                    if ring_len > 0 {
+                        lianli_set_led(&mut packet, position % ring_len, rgb);
+                    }
+                    lianli_write_color_packet(&device, &packet);
+                }
+                lianli_commit(&device, channel, &static_state)?;
+            }
+            sleep_unless_stopped(&stop_flag, tick.saturating_sub(started.elapsed()));
+        }
+        Ok(())
+    })();
+
+    for (channel, fans) in channels {
+        let ch_state = lianli_channel_state(&state, &config, channel);
+        lianli_send_colors(
+            &device,
+            channel,
+            fans,
+            ch_state.fan_color,
+            ch_state.edge_color,
+        );
+        lianli_commit(&device, channel, &ch_state)?;
+    }
+    println!("  LianLi UNI FAN AL V2: Previous state restored");
+    result
+}
+
+/// Blink one channel white/off at 2 Hz so it can be found in the case, then restore it
+fn lianli_identify(channel: u8, duration: Duration) -> Result<()> {
+    let config = Config::load()?;
+    let state = LianliState::load()?;
+    let fans = lianli_channel_fans(&config, channel)?;
+
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = lianli_open(&api)?;
+    let stop_flag = install_stop_handler()?;
+
+    println!(
+        "  LianLi UNI FAN AL V2: Flashing channel {} for {:.1}s",
+        channel,
+        duration.as_secs_f32()
+    );
+    let ch_state = LianliChannelState {
+        mode: lianli::MODE_STATIC,
+        brightness: lianli::BRIGHTNESS_FULL,
+        ..lianli_channel_state(&state, &config, channel)
+    };
+    let half_period = Duration::from_millis(lianli::IDENTIFY_HALF_PERIOD_MS);
+    let started = Instant::now();
+    let mut lit = true;
+    let result = (|| -> Result<()> {
+        while started.elapsed() < duration && !stop_flag.load(Ordering::Relaxed) {
+            let rgb = if lit { Rgb::WHITE } else { Rgb::BLACK };
+            lianli_send_colors(&device, channel, fans, rgb, rgb);
+            lianli_commit(&device, channel, &ch_state)?;
+            lit = !lit;
+            sleep_unless_stopped(&stop_flag, half_period);
+        }
+        Ok(())
+    })();
+
+    // Only this channel was touched; the others keep running undisturbed
+    let ch_state = lianli_channel_state(&state, &config, channel);
+    lianli_send_colors(
+        &device,
+        channel,
+        fans,
+        ch_state.fan_color,
+        ch_state.edge_color,
+    );
+    lianli_commit(&device, channel, &ch_state)?;
+    println!("  LianLi UNI FAN AL V2: Channel {} restored", channel);
+    result
+}
+
+/// Fan RPM per channel
+///
+/// Not available: the RGB interface this tool drives (the one OpenRGB's
+/// LianLiUniHubALController uses) never sends input reports, and the fan
+/// speed side of the AL hub's protocol hasn't been documented. Use
+/// `lianli hub-passthrough` to experiment if you want to find it.
+fn lianli_read_fan_rpms(_device: &HidDevice) -> Result<[u16; lianli::NUM_CHANNELS as usize]> {
+    Err(LightsOutError::Protocol(anyhow!(
+        "The LianLi UNI FAN AL V2 protocol used here has no fan RPM readback"
+    ))
+    .into())
+}
+
+/// Read fan RPMs on every channel as a quick health check
+///
+/// Fan speed can't be set through this tool either, so the fans are
+/// measured at whatever speed the hub is running them.
+fn lianli_fan_test(duration: Duration) -> Result<()> {
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = lianli_open(&api)?;
+
+    let started = Instant::now();
+    while started.elapsed() < duration {
+        let rpms = lianli_read_fan_rpms(&device)?;
+        for (channel, rpm) in rpms.iter().enumerate() {
+            println!("  Channel {}: {} RPM", channel, rpm);
+        }
+        std::thread::sleep(Duration::from_secs(1));
+    }
+    Ok(())
+}
+
+fn lianli_per_channel_effect(
+    channels: &[u8],
+    effects: &[LianliEffect],
+    colors: &[Rgb],
+) -> Result<()> {
+    if effects.len() != channels.len() || colors.len() != channels.len() {
+        return Err(LightsOutError::Config(anyhow!(
+            "Each --channel needs a matching --effect and --color \
+             ({} channels, {} effects, {} colors)",
+            channels.len(),
+            effects.len(),
+            colors.len()
+        ))
+        .into());
+    }
+    let configs: Vec<ChannelConfig> = channels
+        .iter()
+        .zip(effects)
+        .zip(colors)
+        .map(|((&channel, &effect), &color)| ChannelConfig {
+            channel,
+            effect,
+            color,
+        })
+        .collect();
+
+    let config = Config::load()?;
+    let fans = configs
+        .iter()
+        .map(|ch| lianli_channel_fans(&config, ch.channel))
+        .collect::<Result<Vec<u8>>>()?;
+
+    let mut state = LianliState::load()?;
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = lianli_open(&api)?;
+    for (ch, fans) in configs.iter().zip(fans) {
+        lianli_send_colors(&device, ch.channel, fans, ch.color, ch.color);
+        let ch_state = ch.state(lianli_channel_state(&state, &config, ch.channel));
+        lianli_commit(&device, ch.channel, &ch_state)?;
+        state.channels.insert(ch.channel, ch_state);
+        println!(
+            "  LianLi UNI FAN AL V2: Channel {} set to {:?} ({})",
+            ch.channel, ch.effect, ch.color
+        );
+    }
+    state.save()?;
+    Ok(())
+}
+
+fn lianli_color_temp(kelvin: u32, brightness: u8) -> Result<()> {
+    let rgb = kelvin_to_rgb(kelvin).scale(brightness);
+    let config = Config::load()?;
+    let mut state = LianliState::load()?;
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = lianli_open(&api)?;
+
+    lianli_set_color(&device, &config, &mut state, rgb)?;
+    state.save()?;
+
+    println!(
+        "  LianLi UNI FAN AL V2: {} K ({}) at {}%",
+        kelvin, rgb, brightness
+    );
+    Ok(())
+}
+
+fn lianli_port_map(channels: &[u8], fans: &[u8]) -> Result<()> {
+    if channels.len() != fans.len() {
+        return Err(LightsOutError::Config(anyhow!(
+            "Each --channel needs a matching --fans ({} channels, {} fan counts)",
+            channels.len(),
+            fans.len()
+        ))
+        .into());
+    }
+
+    let mut config = Config::load()?;
+    if !channels.is_empty() {
+        for (&channel, &count) in channels.iter().zip(fans) {
+            config.lianli.channels.entry(channel).or_default().fans = count;
+        }
+        config.save()?;
+        println!("Saved port map to {}", Config::path().display());
+    }
+
+    if config.lianli.channels.is_empty() {
+        println!(
+            "No port map configured (all {} channels assumed to have {} fans)",
+            lianli::NUM_CHANNELS,
+            lianli::MAX_FANS_PER_CHANNEL
+        );
+    }
+    for (channel, ch) in &config.lianli.channels {
+        println!("  Channel {}: {} fan(s)", channel, ch.fans);
+    }
+    Ok(())
+}
+
+/// Find the AMDGPU OEM i2c bus by scanning /sys/class/i2c-dev/*/name
+fn find_gpu_i2c_bus() -> Result<String> {
+    let i2c_dev_path = Path::new("/sys/class/i2c-dev");
+
+    for entry in fs::read_dir(i2c_dev_path).context("Failed to read /sys/class/i2c-dev")? {
+        let entry = entry?;
+        let name_path = entry.path().join("name");
+        if let Ok(name) = fs::read_to_string(&name_path) {
+            // Look for "AMDGPU DM i2c OEM bus" or similar
+            if name.contains("AMDGPU") && name.contains("OEM") {
+                let dev_name = entry.file_name();
+                let bus_path = format!("/dev/{}", dev_name.to_string_lossy());
+                return Ok(bus_path);
+            }
+        }
+    }
+
+    Err(LightsOutError::DeviceNotFound(anyhow!(
+        "AMDGPU OEM i2c bus not found. Ensure kernel >= 6.14 with OEM i2c patches."
+    ))
+    .into())
+}
+
+/// ENE controller generations, which differ in where the color registers live
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GpuEneVersion {
+    /// First generation (e.g. "LED-0116"), colors at 0x8000/0x8010
+    V1,
+    /// Newer AUMA0/AUDA0 controllers, colors at 0x8100/0x8160
+    V2,
+}
+
+impl GpuEneVersion {
+    /// Identify the controller from its device name string
+    ///
+    /// Follows OpenRGB: known AUMA0/AUDA0 names use the V2 layout, anything
+    /// else is assumed to be first generation.
+    fn detect(device: &mut LinuxI2CDevice) -> Result<(GpuEneVersion, String)> {
+        let name = ene_read_device_name(device)?;
+        let version = if name.starts_with("AUMA0-") || name.starts_with("AUDA0-") {
+            GpuEneVersion::V2
+        } else {
+            GpuEneVersion::V1
+        };
+        Ok((version, name))
+    }
+
+    fn mode_reg(self) -> u16 {
+        gpu::ENE_REG_MODE
+    }
+
+    fn color_reg(self) -> u16 {
+        match self {
+            GpuEneVersion::V1 => gpu::ENE_REG_COLORS_EFFECT,
+            GpuEneVersion::V2 => gpu::ENE_REG_COLORS_EFFECT_V2,
+        }
+    }
+
+    fn apply_reg(self) -> u16 {
+        gpu::ENE_REG_APPLY
+    }
+}
+
+/// Write one byte to an ENE register
+fn ene_write(device: &mut LinuxI2CDevice, reg: u16, val: u8) -> Result<()> {
+    // Write register address (byte-swapped)
+    device
+        .smbus_write_word_data(gpu::SMBUS_CMD_ADDR, gpu::swap_bytes(reg))
+        .with_context(|| format!("Failed to write register address 0x{:04X}", reg))?;
+    device
+        .smbus_write_byte_data(gpu::SMBUS_CMD_DATA, val)
+        .with_context(|| format!("Failed to write register 0x{:04X}", reg))?;
+    Ok(())
+}
+
+/// Read one byte from an ENE register
+fn ene_read(device: &mut LinuxI2CDevice, reg: u16) -> Result<u8> {
+    device
+        .smbus_write_word_data(gpu::SMBUS_CMD_ADDR, gpu::swap_bytes(reg))
+        .with_context(|| format!("Failed to write register address 0x{:04X}", reg))?;
+    device
+        .smbus_read_byte_data(gpu::SMBUS_CMD_READ)
+        .with_context(|| format!("Failed to read register 0x{:04X}", reg))
+}
+
+/// Read the NUL-terminated controller name at ENE_REG_DEVICE_NAME
+fn ene_read_device_name(device: &mut LinuxI2CDevice) -> Result<String> {
+    let mut name = Vec::new();
+    for i in 0..gpu::ENE_DEVICE_NAME_LEN {
+        let b = ene_read(device, gpu::ENE_REG_DEVICE_NAME + i)?;
+        if b == 0 {
+            break;
+        }
+        name.push(b);
+    }
+    Ok(String::from_utf8_lossy(&name).into_owned())
+}
+
+fn gpu_open() -> Result<LinuxI2CDevice> {
+    let bus_path = find_gpu_i2c_bus()?;
+    println!("  GPU: Found i2c bus at {}", bus_path);
+
+    gpu_open_bus(&bus_path)
+}
+
+fn gpu_open_bus(bus_path: &str) -> Result<LinuxI2CDevice> {
+    LinuxI2CDevice::new(bus_path, gpu::ENE_I2C_ADDR).context("Failed to open GPU i2c device")
+}
+
+/// Number of LEDs from the controller's config table
+fn ene_led_count(device: &mut LinuxI2CDevice) -> Result<u8> {
+    ene_read(
+        device,
+        gpu::ENE_REG_CONFIG_TABLE + gpu::ENE_CONFIG_LED_COUNT,
+    )
+}
+
+/// Registers that make up the visible LED state: mode, speed, direction and effect colors
+fn ene_state_registers(device: &mut LinuxI2CDevice) -> Result<Vec<u16>> {
+    let (version, _) = GpuEneVersion::detect(device)?;
+    let led_count = ene_led_count(device)? as u16;
+    let mut regs = vec![
+        gpu::ENE_REG_MODE,
+        gpu::ENE_REG_SPEED,
+        gpu::ENE_REG_DIRECTION,
+    ];
+    regs.extend(version.color_reg()..version.color_reg() + led_count * 3);
+    Ok(regs)
+}
+
+/// Snapshot the current LED registers to the GPU state file. LEDs that are
+/// already off are skipped so a repeated disable keeps the last lit state.
+fn gpu_save_state(device: &mut LinuxI2CDevice) -> Result<()> {
+    if ene_read(device, gpu::ENE_REG_MODE)? == gpu::ENE_MODE_OFF {
+        return Ok(());
+    }
+    let mut state = GpuState::now();
+    for reg in ene_state_registers(device)? {
+        state.registers.insert(reg, ene_read(device, reg)?);
+    }
+    state.save()
+}
+
+/// Disable the GPU LEDs over i2c, falling back to sysfs Aura LEDs if allowed
+fn gpu_disable_with_fallback(protocol: GpuProtocol, allow_fallback: bool) -> Result<()> {
+    match gpu_disable(protocol) {
+        Err(e) if allow_fallback => {
+            println!("  GPU: i2c failed ({}), trying sysfs LEDs", e);
+            gpu_fallback_via_sysfs(None)
+        }
+        result => result,
+    }
+}
+
+/// ASUS Aura LEDs registered by asus-wmi in the LED class
+fn find_aura_leds() -> Result<Vec<std::path::PathBuf>> {
+    let leds_path = Path::new("/sys/class/leds");
+    let mut leds = Vec::new();
+    for entry in fs::read_dir(leds_path).context("Failed to read /sys/class/leds")? {
+        let entry = entry?;
+        if entry
+            .file_name()
+            .to_string_lossy()
+            .starts_with("asus::aura")
+        {
+            leds.push(entry.path());
+        }
+    }
+    leds.sort();
+    Ok(leds)
+}
+
+/// Turn an LED class device off by writing 0 to its brightness file
+fn gpu_disable_via_sysfs(led_path: &Path) -> Result<()> {
+    let brightness = if led_path.is_dir() {
+        led_path.join("brightness")
+    } else {
+        led_path.to_path_buf()
+    };
+    fs::write(&brightness, "0").with_context(|| format!("Failed to write {}", brightness.display()))
+}
+
+fn gpu_fallback_via_sysfs(path: Option<&Path>) -> Result<()> {
+    let leds = match path {
+        Some(path) => vec![path.to_path_buf()],
+        None => find_aura_leds()?,
+    };
+    if leds.is_empty() {
+        return Err(LightsOutError::DeviceNotFound(anyhow!(
+            "No asus::aura LEDs found in /sys/class/leds (is asus-wmi loaded?)"
+        ))
+        .into());
+    }
+    for led in &leds {
+        gpu_disable_via_sysfs(led)?;
+        println!("  GPU: {} disabled", led.display());
+    }
+    Ok(())
+}
+
+/// Identify the RGB controller on the GPU's OEM i2c bus
+///
+/// Only ENE controllers can be recognized so far; they answer with a device
+/// name string at ENE_REG_DEVICE_NAME.
+fn gpu_detect_controller() -> Result<GpuProtocol> {
+    let mut device = gpu_open()?;
+    match GpuEneVersion::detect(&mut device) {
+        Ok((GpuEneVersion::V1, _)) => Ok(GpuProtocol::Ene30),
+        Ok((GpuEneVersion::V2, _)) => Ok(GpuProtocol::Ene40),
+        Err(e) => Err(LightsOutError::DeviceNotFound(anyhow!(
+            "No ENE controller answered at 0x{:02X} ({:#}); other controller types \
+             must be selected with --protocol",
+            gpu::ENE_I2C_ADDR,
+            e
+        ))
+        .into()),
+    }
+}
+
+/// Disable the GPU LEDs with the implementation for its controller type
+fn gpu_disable(protocol: GpuProtocol) -> Result<()> {
+    match protocol {
+        // Some controllers take the mode write but don't answer the name read,
+        // so an undetected controller still gets the ENE off write
+        GpuProtocol::Auto => match gpu_detect_controller() {
+            Ok(protocol) => gpu_disable(protocol),
+            Err(e) => {
+                eprintln!("  Warning: {:#}; trying the ENE protocol anyway", e);
+                gpu_ene_disable()
+            }
+        },
+        // Both ENE layouts share the mode and apply registers
+        GpuProtocol::Ene30 | GpuProtocol::Ene40 => gpu_ene_disable(),
+        GpuProtocol::Ite => gpu_ite_disable(),
+        GpuProtocol::Nuvoton => gpu_nuvoton_disable(),
+    }
+}
+
+/// Print the outcome of one write-sequence step (successes only when verbose)
+fn gpu_sequence_step<T>(verbose: bool, step: &str, result: Result<T>) -> Result<T> {
+    match &result {
+        Ok(_) if verbose => println!("  [ok]   {}", step),
+        Ok(_) => {}
+        Err(e) => println!("  [fail] {}: {:#}", step, e),
+    }
+    result.with_context(|| format!("Write sequence failed at: {}", step))
+}
+
+/// Turn the LEDs off with every SMBus transaction of the ENE protocol as its own step
+///
+/// Same writes as `gpu_ene_disable` (plus black for LED 0), split up so a
+/// controller that ignores them shows which transaction it drops. The mode is
+/// read back at the end, because a wedged controller can accept every write
+/// without acting on it.
+fn gpu_test_write_sequence(device: &mut LinuxI2CDevice, verbose: bool) -> Result<()> {
+    gpu_sequence_step(
+        verbose,
+        &format!("Probe address 0x{:02X}", gpu::ENE_I2C_ADDR),
+        device.smbus_write_quick(false).context("No acknowledge"),
+    )?;
+    let (version, name) = gpu_sequence_step(
+        verbose,
+        "Read controller name",
+        GpuEneVersion::detect(device),
+    )?;
+    if verbose {
+        println!("         {} ({:?} register layout)", name, version);
+    }
+
+    gpu_sequence_step(
+        verbose,
+        &format!("Select mode register 0x{:04X}", version.mode_reg()),
+        device
+            .smbus_write_word_data(gpu::SMBUS_CMD_ADDR, gpu::swap_bytes(version.mode_reg()))
+            .map_err(anyhow::Error::from),
+    )?;
+    gpu_sequence_step(
+        verbose,
+        &format!("Write mode 0x{:02X} (off)", gpu::ENE_MODE_OFF),
+        device
+            .smbus_write_byte_data(gpu::SMBUS_CMD_DATA, gpu::ENE_MODE_OFF)
+            .map_err(anyhow::Error::from),
+    )?;
+    gpu_sequence_step(
+        verbose,
+        &format!("Write LED 0 color at 0x{:04X} (black)", version.color_reg()),
+        (0..3).try_for_each(|i| ene_write(device, version.color_reg() + i, 0)),
+    )?;
+    gpu_sequence_step(
+        verbose,
+        &format!("Write apply register 0x{:04X}", version.apply_reg()),
+        ene_write(device, version.apply_reg(), gpu::ENE_APPLY_VAL),
+    )?;
+    gpu_sequence_step(
+        verbose,
+        "Read back mode register",
+        ene_read(device, version.mode_reg()).and_then(|mode| {
+            if mode == gpu::ENE_MODE_OFF {
+                Ok(())
+            } else {
+                Err(LightsOutError::Protocol(anyhow!(
+                    "Mode is 0x{:02X}, expected 0x{:02X}",
+                    mode,
+                    gpu::ENE_MODE_OFF
+                ))
+                .into())
+            }
+        }),
+    )?;
+
+    println!("  GPU: Write sequence completed; LEDs off");
+    Ok(())
+}
+
+fn gpu_ite_disable() -> Result<()> {
+    Err(
+        LightsOutError::Protocol(anyhow!("ITE IT8291 GPU controllers are not supported yet"))
+            .into(),
+    )
+}
+
+fn gpu_nuvoton_disable() -> Result<()> {
+    Err(LightsOutError::Protocol(anyhow!(
+        "Nuvoton NCT3011T GPU controllers are not supported yet"
+    ))
+    .into())
+}
+
+fn gpu_ene_disable() -> Result<()> {
+    let mut device = gpu_open()?;
+
+    // ENE registers can be read back, so remember what was there for `gpu enable`.
+    // A failed snapshot shouldn't stop the LEDs from being turned off.
+    if let Err(e) = gpu_save_state(&mut device) {
+        eprintln!("  GPU: Could not save LED state: {:#}", e);
+    }
+
+    // Set LED mode to OFF, then apply changes
+    ene_write(&mut device, gpu::ENE_REG_MODE, gpu::ENE_MODE_OFF)?;
+    ene_write(&mut device, gpu::ENE_REG_APPLY, gpu::ENE_APPLY_VAL)?;
+
+    println!("  GPU: LEDs disabled");
+    Ok(())
+}
+
+fn gpu_enable(mode: Option<GpuLedMode>, rgb: Option<Rgb>) -> Result<()> {
+    let mut device = gpu_open()?;
+
+    if mode.is_none() && rgb.is_none() {
+        let state = GpuState::load()?.ok_or_else(|| {
+            LightsOutError::Config(anyhow!(
+                "No saved GPU state at {} (use --mode/--rgb)",
+                GpuState::path().display()
+            ))
+        })?;
+        for (&reg, &val) in &state.registers {
+            ene_write(&mut device, reg, val)?;
+        }
+        ene_write(&mut device, gpu::ENE_REG_APPLY, gpu::ENE_APPLY_VAL)?;
+        println!(
+            "  GPU: Restored {} registers saved at {}",
+            state.registers.len(),
+            state.timestamp
+        );
+        return Ok(());
+    }
+
+    let mode = mode.unwrap_or(GpuLedMode::Static);
+    ene_set_mode(&mut device, mode, rgb)?;
+    gpu_save_state(&mut device)?;
+
+    println!("  GPU: LEDs set to {:?}", mode);
+    Ok(())
+}
+
+fn gpu_set_mode(mode: GpuLedMode, rgb: Option<Rgb>) -> Result<()> {
+    if rgb.is_some() && !mode.takes_color() {
+        return Err(LightsOutError::Config(anyhow!(
+            "--rgb only applies to static and breathing, not {:?}",
+            mode
+        ))
+        .into());
+    }
+
+    let mut device = gpu_open()?;
+    // Like `gpu off`, keep the lit state so `gpu enable` can bring it back
+    if mode == GpuLedMode::Off {
+        gpu_save_state(&mut device)?;
+    }
+    ene_set_mode(&mut device, mode, rgb)?;
+    if mode != GpuLedMode::Off {
+        gpu_save_state(&mut device)?;
+    }
+
+    match rgb {
+        Some(rgb) => println!("  GPU: Mode set to {:?} {}", mode, rgb),
+        None => println!("  GPU: Mode set to {:?}", mode),
+    }
+    Ok(())
+}
+
+/// Effect color of the first LED, read back from the controller
+fn gpu_read_color(device: &mut LinuxI2CDevice) -> Result<Rgb> {
+    let (version, _) = GpuEneVersion::detect(device)?;
+    let reg = version.color_reg();
+    // Colors are stored in R, B, G order
+    let r = ene_read(device, reg)?;
+    let b = ene_read(device, reg + 1)?;
+    let g = ene_read(device, reg + 2)?;
+    Ok(Rgb::new(r, g, b))
+}
+
+/// Effect color of the first LED in the GPU state file, which is written
+/// whenever the LEDs are set
+fn gpu_cached_color() -> Result<Option<Rgb>> {
+    let Some(state) = GpuState::load()? else {
+        return Ok(None);
+    };
+    for base in [gpu::ENE_REG_COLORS_EFFECT, gpu::ENE_REG_COLORS_EFFECT_V2] {
+        let reg = |offset| state.registers.get(&(base + offset)).copied();
+        if let (Some(r), Some(b), Some(g)) = (reg(0), reg(1), reg(2)) {
+            return Ok(Some(Rgb::new(r, g, b)));
+        }
+    }
+    Ok(None)
+}
+
+/// Write a system file, mapping permission errors to the permission exit code
+fn write_system_file(path: &str, content: &str, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let result = Path::new(path)
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| fs::write(path, content))
+        .and_then(|()| fs::set_permissions(path, fs::Permissions::from_mode(mode)));
+    result.map_err(|e| match e.kind() {
+        io::ErrorKind::PermissionDenied => {
+            LightsOutError::Permission(anyhow!("No permission to write {} (run as root)", path))
+        }
+        _ => LightsOutError::Io(anyhow!("Failed to write {}: {}", path, e)),
+    })?;
+    println!("  Wrote {}", path);
+    Ok(())
+}
+
+/// Run a reload command after (un)installing; failures are only warned about
+fn run_reload_command(program: &str, args: &[&str]) {
+    match std::process::Command::new(program).args(args).status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => eprintln!(
+            "  Warning: {} {} exited with {}",
+            program,
+            args.join(" "),
+            status
+        ),
+        Err(e) => eprintln!("  Warning: Failed to run {}: {}", program, e),
+    }
+}
+
+fn gpu_disable_on_boot_install() -> Result<()> {
+    let exe = std::env::current_exe().context("Failed to find the lights-out binary")?;
+    let exe_str = exe
+        .to_str()
+        .ok_or_else(|| LightsOutError::Config(anyhow!("{} is not valid UTF-8", exe.display())))?;
+    let unit = gpu::BOOT_UNIT.replace("@LEDCTL@", &systemd::quote_exec_arg(exe_str));
+    let generator = gpu::BOOT_GENERATOR.replace("@UNIT@", unit.trim_end());
+
+    write_system_file(gpu::BOOT_GENERATOR_PATH, &generator, 0o755)?;
+    write_system_file(gpu::BOOT_UDEV_RULE_PATH, gpu::BOOT_UDEV_RULE, 0o644)?;
+    write_system_file(gpu::BOOT_MODULES_LOAD_PATH, "i2c-dev\n", 0o644)?;
+    run_reload_command("systemctl", &["daemon-reload"]);
+    run_reload_command("udevadm", &["control", "--reload"]);
+
+    println!(
+        "  GPU: LEDs will be turned off at boot by {} gpu",
+        exe.display()
+    );
+    Ok(())
+}
+
+fn gpu_disable_on_boot_uninstall() -> Result<()> {
+    for path in [
+        gpu::BOOT_GENERATOR_PATH,
+        gpu::BOOT_UDEV_RULE_PATH,
+        gpu::BOOT_MODULES_LOAD_PATH,
+    ] {
+        match fs::remove_file(path) {
+            Ok(()) => println!("  Removed {}", path),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                return Err(LightsOutError::Permission(anyhow!(
+                    "No permission to remove {} (run as root)",
+                    path
+                ))
+                .into())
+            }
+            Err(e) => {
+                return Err(LightsOutError::Io(anyhow!("Failed to remove {}: {}", path, e)).into())
+            }
+        }
+    }
+    run_reload_command("systemctl", &["daemon-reload"]);
+    run_reload_command("udevadm", &["control", "--reload"]);
+    Ok(())
+}
+
+fn gpu_print_color() -> Result<()> {
+    let mut device = gpu_open()?;
+    let rgb = match gpu_read_color(&mut device) {
+        Ok(rgb) => rgb,
+        Err(e) => {
+            eprintln!("  Warning: {:#}; using the saved GPU state", e);
+            gpu_cached_color()?.ok_or_else(|| {
+                LightsOutError::Protocol(anyhow!(
+                    "Color can't be read back and no GPU state is saved at {}",
+                    GpuState::path().display()
+                ))
+            })?
+        }
+    };
+
+    println!("  GPU: Color R={} G={} B={} ({})", rgb.r, rgb.g, rgb.b, rgb);
+    Ok(())
+}
+
+/// Write an effect mode (and optionally a color for every LED), then apply it
+fn ene_set_mode(device: &mut LinuxI2CDevice, mode: GpuLedMode, rgb: Option<Rgb>) -> Result<()> {
+    if let Some(rgb) = rgb {
+        let (version, _) = GpuEneVersion::detect(device)?;
+        let led_count = ene_led_count(device)? as u16;
+        for led in 0..led_count {
+            // Colors are stored in R, B, G order
+            let reg = version.color_reg() + led * 3;
+            ene_write(device, reg, rgb.r)?;
+            ene_write(device, reg + 1, rgb.b)?;
+            ene_write(device, reg + 2, rgb.g)?;
+        }
+    }
+    ene_write(device, gpu::ENE_REG_MODE, mode as u8)?;
+    ene_write(device, gpu::ENE_REG_APPLY, gpu::ENE_APPLY_VAL)
+}
+
+/// Whether /proc/modules lists a loaded module (names use underscores there)
+fn kernel_module_loaded(modules: &str, name: &str) -> bool {
+    modules
+        .lines()
+        .any(|line| line.split_whitespace().next() == Some(name))
+}
+
+/// Name of a group from /etc/group, or the numeric ID if it isn't listed
+fn group_name(gid: u32) -> String {
+    fs::read_to_string("/etc/group")
+        .ok()
+        .and_then(|groups| {
+            groups.lines().find_map(|line| {
+                let fields: Vec<&str> = line.split(':').collect();
+                (fields.len() >= 3 && fields[2] == gid.to_string()).then(|| fields[0].to_string())
+            })
+        })
+        .unwrap_or_else(|| gid.to_string())
+}
+
+/// Whether /etc/group lists `user` as a member of `group`
+fn group_lists_user(group: &str, user: &str) -> bool {
+    fs::read_to_string("/etc/group").is_ok_and(|groups| {
+        groups.lines().any(|line| {
+            let fields: Vec<&str> = line.split(':').collect();
+            fields.len() >= 4 && fields[0] == group && fields[3].split(',').any(|u| u == user)
+        })
+    })
+}
+
+/// Effective UID and supplementary group IDs of this process
+fn process_ids() -> (u32, Vec<u32>) {
+    let status = fs::read_to_string("/proc/self/status").unwrap_or_default();
+    let field = |name: &str| -> Vec<u32> {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .map(|ids| {
+                ids.split_whitespace()
+                    .filter_map(|id| id.parse().ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    // Uid: real, effective, saved, filesystem
+    let euid = field("Uid:").get(1).copied().unwrap_or(u32::MAX);
+    let mut groups = field("Groups:");
+    groups.extend(field("Gid:").get(1));
+    (euid, groups)
+}
+
+/// Walk through everything non-root access to the GPU i2c bus depends on
+fn gpu_check_permissions() -> Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    // (check, fix if it failed)
+    let mut checks: Vec<(String, Option<String>)> = Vec::new();
+    let modules = fs::read_to_string("/proc/modules").unwrap_or_default();
+
+    let i2c_dev =
+        kernel_module_loaded(&modules, "i2c_dev") || Path::new("/sys/class/i2c-dev").exists();
+    checks.push((
+        "i2c-dev kernel module loaded".to_string(),
+        (!i2c_dev).then(|| {
+            "sudo modprobe i2c-dev, and list it in /etc/modules-load.d/ to load it at boot"
+                .to_string()
+        }),
+    ));
+    checks.push((
+        "amdgpu driver loaded".to_string(),
+        (!kernel_module_loaded(&modules, "amdgpu"))
+            .then(|| "sudo modprobe amdgpu (the LEDs are only reachable on AMD GPUs)".to_string()),
+    ));
+
+    let bus = find_gpu_i2c_bus();
+    checks.push((
+        "AMDGPU OEM i2c bus present".to_string(),
+        bus.as_ref().err().map(|_| {
+            "Use kernel 6.14 or later, which exposes the OEM i2c bus (see `ls /sys/class/i2c-dev`)"
+                .to_string()
+        }),
+    ));
+
+    if let Ok(bus) = &bus {
+        match fs::metadata(bus) {
+            Err(_) => checks.push((
+                format!("{} exists", bus),
+                Some("The bus has no device node; load i2c-dev and check udev".to_string()),
+            )),
+            Ok(meta) => {
+                let group = group_name(meta.gid());
+                checks.push((format!("{} exists", bus), None));
+                checks.push((
+                    format!(
+                        "{} is readable and writable (mode {:o}, group {})",
+                        bus,
+                        meta.mode() & 0o777,
+                        group
+                    ),
+                    fs::OpenOptions::new()
+                        .read(true)
+                        .write(true)
+                        .open(bus)
+                        .err()
+                        .map(|_| {
+                            "Add a udev rule to /etc/udev/rules.d/99-i2c.rules: \
+                             KERNEL==\"i2c-[0-9]*\", GROUP=\"i2c\", MODE=\"0660\" \
+                             (sudo groupadd -f i2c; sudo udevadm trigger)"
+                                .to_string()
+                        }),
+                ));
+
+                let (euid, groups) = process_ids();
+                let user = std::env::var("USER").unwrap_or_else(|_| "$USER".to_string());
+                let in_group = euid == 0 || groups.contains(&meta.gid());
+                checks.push((
+                    format!("Current user is in group {}", group),
+                    (!in_group).then(|| {
+                        if group_lists_user(&group, &user) {
+                            format!(
+                                "Log out and back in (or run `newgrp {}`) to pick up the group",
+                                group
+                            )
+                        } else {
+                            format!(
+                                "sudo usermod -aG {} {}, then log out and back in",
+                                group, user
+                            )
+                        }
+                    }),
+                ));
+            }
+        }
+    }
+
+    let mut failed = 0;
+    for (check, fix) in &checks {
+        match fix {
+            None => println!("  [ok]   {}", check),
+            Some(fix) => {
+                failed += 1;
+                println!("  [fail] {}", check);
+                println!("         Fix: {}", fix);
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(LightsOutError::Permission(anyhow!(
+            "{} of {} checks failed",
+            failed,
+            checks.len()
+        ))
+        .into());
+    }
+    println!("  All checks passed");
+    Ok(())
+}
+
+/// Repeatedly read the (read-only) device name register to gauge i2c bus reliability
+fn gpu_i2c_health_check(count: u32) -> Result<()> {
+    let mut device = gpu_open()?;
+
+    let mut latencies = Vec::with_capacity(count as usize);
+    let mut errors = 0u32;
+    let mut mismatches = 0u32;
+    let mut expected = None;
+    let mut streak = 0u32;
+    let mut longest_streak = 0u32;
+    let mut first_failure: Option<Instant> = None;
+    let mut recoveries = Vec::new();
+
+    for _ in 0..count {
+        let start = Instant::now();
+        match ene_read(&mut device, gpu::ENE_REG_DEVICE_NAME) {
+            Ok(val) => {
+                latencies.push(start.elapsed());
+                // The name never changes, so a different byte means a corrupted transfer
+                if *expected.get_or_insert(val) != val {
+                    mismatches += 1;
+                }
+                if let Some(failed_at) = first_failure.take() {
+                    recoveries.push(failed_at.elapsed());
+                }
+                streak = 0;
+            }
+            Err(e) => {
+                errors += 1;
+                streak += 1;
+                longest_streak = longest_streak.max(streak);
+                first_failure.get_or_insert(start);
+                if errors <= gpu::HEALTH_CHECK_MAX_ERRORS_SHOWN {
+                    eprintln!("  Warning: {:#}", e);
+                }
+            }
+        }
+    }
+
+    let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+    println!("  GPU i2c health check ({} transactions)", count);
+    println!("    Errors:         {}", errors);
+    println!("    Bad reads:      {}", mismatches);
+    if let (Some(min), Some(max)) = (latencies.iter().min(), latencies.iter().max()) {
+        let mean = latencies.iter().sum::<Duration>() / latencies.len() as u32;
+        println!(
+            "    Latency:        min {:.2} ms, mean {:.2} ms, max {:.2} ms",
+            ms(*min),
+            ms(mean),
+            ms(*max)
+        );
+    }
+    if let Some(max) = recoveries.iter().max() {
+        println!("    Recovery time:  up to {:.2} ms", ms(*max));
+    }
+    if first_failure.is_some() {
+        println!("    Recovery time:  bus had not recovered by the end of the run");
+    }
+    println!(
+        "    Longest streak: {} consecutive error(s)",
+        longest_streak
+    );
+
+    let failed = errors + mismatches;
+    let score = 100.0 * (count - failed.min(count)) as f64 / count as f64;
+    let verdict = if failed == 0 {
+        "healthy"
+    } else if longest_streak >= gpu::HEALTH_CHECK_REPEATED_ERRORS {
+        "repeated errors, likely a hardware or driver problem"
+    } else {
+        "occasional glitches"
+    };
+    println!("    Health score:   {:.1}% ({})", score, verdict);
+    Ok(())
+}
+
+/// Result of probing one i2c address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum I2cProbe {
+    Ack,
+    NoAck,
+    /// Claimed by a kernel driver, so not probed
+    Busy,
+}
+
+/// Probe the usual 7-bit address range on an i2c bus, like `i2cdetect -y`
+///
+/// EEPROM ranges are probed with a read byte and everything else with a
+/// quick write, as i2cdetect does by default.
+fn gpu_enumerate_slaves(bus: Option<String>, yes: bool) -> Result<()> {
+    let bus_path = match bus {
+        Some(bus) => bus,
+        None => find_gpu_i2c_bus()?,
+    };
+    fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&bus_path)
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::PermissionDenied => LightsOutError::Permission(anyhow!(
+                "No access to {} (run as root or join the i2c group)",
+                bus_path
+            )),
+            _ => LightsOutError::Io(anyhow!("Failed to open {}: {}", bus_path, e)),
+        })?;
+
+    if !yes {
+        print!(
+            "  Probing every address on {} can confuse some devices. Continue? [y/N] ",
+            bus_path
+        );
+        io::Write::flush(&mut io::stdout())?;
+        let mut line = String::new();
+        io::stdin().read_line(&mut line)?;
+        if !line.trim().eq_ignore_ascii_case("y") {
+            println!("  Aborted");
+            return Ok(());
+        }
+    }
+
+    let mut results = Vec::new();
+    for addr in gpu::I2C_SCAN_FIRST..=gpu::I2C_SCAN_LAST {
+        let probe = match LinuxI2CDevice::new(&bus_path, addr) {
+            Err(LinuxI2CError::Errno(errno)) if errno == libc::EBUSY => I2cProbe::Busy,
+            Err(e) => return Err(e).with_context(|| format!("Failed to address 0x{:02x}", addr)),
+            Ok(mut device) => {
+                let eeprom = (0x30..=0x37).contains(&addr) || (0x50..=0x5F).contains(&addr);
+                let ack = if eeprom {
+                    device.smbus_read_byte().is_ok()
+                } else {
+                    device.smbus_write_quick(false).is_ok()
+                };
+                if ack {
+                    I2cProbe::Ack
+                } else {
+                    I2cProbe::NoAck
+                }
+            }
+        };
+        results.push((addr, probe));
+    }
+
+    println!("     0  1  2  3  4  5  6  7  8  9  a  b  c  d  e  f");
+    for row in (0..0x80u16).step_by(16) {
+        print!("{:02x}:", row);
+        for addr in row..row + 16 {
+            match results.iter().find(|&&(a, _)| a == addr) {
+                Some((_, I2cProbe::Ack)) => print!(" {:02x}", addr),
+                Some((_, I2cProbe::Busy)) => print!(" UU"),
+                Some((_, I2cProbe::NoAck)) => print!(" --"),
+                None => print!("   "),
+            }
+        }
+        println!();
+    }
+
+    println!();
+    for &(addr, probe) in &results {
+        if probe == I2cProbe::NoAck {
+            continue;
+        }
+        let known = gpu::KNOWN_I2C_DEVICES
+            .iter()
+            .find(|&&(known, _)| known == addr)
+            .map_or("", |&(_, name)| name);
+        let busy = if probe == I2cProbe::Busy {
+            " (in use by a kernel driver)"
+        } else {
+            ""
+        };
+        println!("  0x{:02x}: {}{}", addr, known, busy);
+    }
+    Ok(())
+}
+
+fn gpu_auto_detect_ene() -> Result<()> {
+    let mut device = gpu_open()?;
+    let (version, name) = GpuEneVersion::detect(&mut device)?;
+
+    println!("  GPU: ENE controller {:?} ({:?})", name, version);
+    println!("    Mode register:  0x{:04X}", version.mode_reg());
+    println!("    Color register: 0x{:04X}", version.color_reg());
+    println!("    Apply register: 0x{:04X}", version.apply_reg());
+    Ok(())
+}
+
+/// Name of a known ENE register, or None if its purpose is unknown
+fn ene_register_name(reg: u16) -> Option<String> {
+    // Colors are stored as R, B, G triples (from OpenRGB)
+    let color_name = |base: u16, kind: &str| {
+        let i = reg - base;
+        format!(
+            "{} color LED {} {}",
+            kind,
+            i / 3,
+            ["R", "B", "G"][(i % 3) as usize]
+        )
+    };
+    match reg {
+        gpu::ENE_REG_DIRECT => Some("direct mode enable".into()),
+        gpu::ENE_REG_MODE => Some("mode".into()),
+        gpu::ENE_REG_SPEED => Some("speed".into()),
+        gpu::ENE_REG_DIRECTION => Some("direction".into()),
+        gpu::ENE_REG_APPLY => Some("apply".into()),
+        r if (gpu::ENE_REG_COLORS_DIRECT..gpu::ENE_REG_COLORS_EFFECT).contains(&r) => {
+            Some(color_name(gpu::ENE_REG_COLORS_DIRECT, "direct"))
+        }
+        r if (gpu::ENE_REG_COLORS_EFFECT..gpu::ENE_REG_DIRECT).contains(&r) => {
+            Some(color_name(gpu::ENE_REG_COLORS_EFFECT, "effect"))
+        }
+        _ => None,
+    }
+}
+
+/// Decodes ENE register accesses in i2c trace lines
+///
+/// Register writes select an address with command 0x00 (word, high byte
+/// first on the wire); the following 0x01 write or 0x81 read applies to it.
+#[derive(Default)]
+struct EneTraceDecoder {
+    register: Option<u16>,
+}
+
+impl EneTraceDecoder {
+    /// Describe the ENE access in one trace line, if it is one
+    fn decode(&mut self, line: &str) -> Option<String> {
+        let addr = format!("a={:03x}", gpu::ENE_I2C_ADDR);
+        let is_write = line.contains("i2c_write:") || line.contains("smbus_write:");
+        if !line.contains(&addr) || !(is_write || line.contains("smbus_read:")) {
+            return None;
+        }
+        let bytes: Vec<u8> = line
+            .rsplit_once('[')
+            .map(|(_, data)| {
+                data.trim_end_matches(']')
+                    .split('-')
+                    .filter_map(|b| u8::from_str_radix(b.trim(), 16).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        // smbus_* events carry the command separately as "c=<hex>"
+        let (cmd, data) = match line.split_whitespace().find_map(|f| f.strip_prefix("c=")) {
+            Some(c) => (u8::from_str_radix(c, 16).ok()?, &bytes[..]),
+            None => (*bytes.first()?, &bytes[1..]),
+        };
+        let name = |reg: u16| ene_register_name(reg).unwrap_or_else(|| "unknown".into());
+        match (cmd, data) {
+            (gpu::SMBUS_CMD_ADDR, [hi, lo, ..]) => {
+                let reg = u16::from_be_bytes([*hi, *lo]);
+                self.register = Some(reg);
+                Some(format!("select 0x{:04X} ({})", reg, name(reg)))
+            }
+            (gpu::SMBUS_CMD_DATA, [val, ..]) => {
+                let reg = self.register?;
+                Some(format!("0x{:04X} ({}) = 0x{:02X}", reg, name(reg), val))
+            }
+            (gpu::SMBUS_CMD_READ, _) => {
+                let reg = self.register?;
+                Some(format!("read 0x{:04X} ({})", reg, name(reg)))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn gpu_monitor_i2c_traffic(bus: Option<String>, duration: Duration) -> Result<()> {
+    let bus = match bus {
+        Some(bus) => bus,
+        None => find_gpu_i2c_bus()?,
+    };
+    let adapter: u32 = bus
+        .rsplit_once("i2c-")
+        .and_then(|(_, n)| n.parse().ok())
+        .ok_or_else(|| LightsOutError::Config(anyhow!("Can't get the bus number from {}", bus)))?;
+
+    let tracefs = gpu::TRACEFS_PATHS
+        .iter()
+        .map(Path::new)
+        .find(|p| p.join("events/i2c").is_dir())
+        .ok_or_else(|| {
+            LightsOutError::DeviceNotFound(anyhow!(
+                "i2c trace events not found (is tracefs mounted and are you root?)"
+            ))
+        })?;
+    let events = tracefs.join("events/i2c");
+    let write = |path: &Path, value: &str| {
+        fs::write(path, value).with_context(|| format!("Failed to write {}", path.display()))
+    };
+
+    // Same filter for i2c_* and smbus_* events
+    write(
+        &events.join("filter"),
+        &format!("adapter_nr == {}", adapter),
+    )?;
+    write(&tracefs.join("trace"), "")?;
+    write(&events.join("enable"), "1")?;
+
+    let stop_flag = install_stop_handler()?;
+    println!(
+        "  Capturing i2c traffic on {} for {:?} (Ctrl+C to stop)...",
+        bus, duration
+    );
+    sleep_unless_stopped(&stop_flag, duration);
+
+    // Always turn tracing back off, even if reading the buffer fails
+    let disabled = write(&events.join("enable"), "0").and(write(&events.join("filter"), "0"));
+    let trace = fs::read_to_string(tracefs.join("trace"))
+        .with_context(|| format!("Failed to read {}", tracefs.join("trace").display()))?;
+    disabled?;
+
+    let mut decoder = EneTraceDecoder::default();
+    let mut count = 0;
+    for line in trace.lines().filter(|l| !l.starts_with('#')) {
+        match decoder.decode(line) {
+            Some(ene) => println!("{}  ; ENE {}", line.trim(), ene),
+            None => println!("{}", line.trim()),
+        }
+        count += 1;
+    }
+    println!("{} event(s)", count);
+    Ok(())
+}
+
+fn gpu_ene_dump(watch: bool) -> Result<()> {
+    let mut device = gpu_open()?;
+    loop {
+        let mut values = Vec::with_capacity(gpu::ENE_DUMP_LEN as usize);
+        for reg in gpu::ENE_DUMP_START..gpu::ENE_DUMP_START + gpu::ENE_DUMP_LEN {
+            values.push(ene_read(&mut device, reg)?);
+        }
+
+        if watch {
+            // Clear the screen and redraw in place
+            print!("\x1b[2J\x1b[H");
+        }
+        // Known registers are marked with '*'
+        for (i, chunk) in values.chunks(16).enumerate() {
+            let row = gpu::ENE_DUMP_START + (i * 16) as u16;
+            print!("{:04x}: ", row);
+            for (j, b) in chunk.iter().enumerate() {
+                let known = ene_register_name(row + j as u16).is_some();
+                print!("{:02x}{}", b, if known { '*' } else { ' ' });
+            }
+            println!();
+        }
+        println!();
+        for (i, &b) in values.iter().enumerate() {
+            let reg = gpu::ENE_DUMP_START + i as u16;
+            if let Some(name) = ene_register_name(reg) {
+                println!("  0x{:04X} = 0x{:02X}  {}", reg, b, name);
+            }
+        }
+
+        if !watch {
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_secs(1));
+    }
+}
+
+/// Send a readback command to the MSI cooler and return its reply
+fn msi_request(device: &HidDevice, cmd: u8) -> Result<[u8; msi::HID_REPORT_LEN]> {
+    let mut buf = [0u8; msi::HID_REPORT_LEN];
+    buf[0] = msi::CMD_PREFIX;
+    buf[1] = cmd;
+    device
+        .write(&buf)
+        .with_context(|| format!("Failed to send command 0x{:02X}", cmd))?;
+
+    let mut reply = [0u8; msi::HID_REPORT_LEN];
+    let n = device
+        .read_timeout(&mut reply, msi::READ_TIMEOUT_MS)
+        .with_context(|| format!("Failed to read reply to command 0x{:02X}", cmd))?;
+    if n < 2 || reply[0] != msi::CMD_PREFIX || reply[1] != cmd {
+        return Err(LightsOutError::Protocol(anyhow!(
+            "Unexpected reply to command 0x{:02X}: {:02x?}",
+            cmd,
+            &reply[..n.min(8)]
+        ))
+        .into());
+    }
+    Ok(reply)
 }
 
-fn msi_disable() -> Result<()> {
-    let api = HidApi::new().context("Failed to initialize HID API")?;
-    let device = api
-        .open(msi::VID, msi::PID)
-        .context("Failed to open MSI CORELIQUID")?;
+#[derive(Debug, Clone, Serialize)]
+struct MsiFanReading {
+    name: &'static str,
+    rpm: u16,
+    duty_pct: u16,
+}
 
-    // Disable LEDs via feature report
-    let mut buf = [0u8; msi::MAX_DATA_LEN];
-    buf[0] = msi::FEATURE_REPORT_ID;
-    device
-        .get_feature_report(&mut buf)
-        .context("Failed to get feature report")?;
+fn msi_read_fan_status(device: &HidDevice) -> Result<Vec<MsiFanReading>> {
+    let reply = msi_request(device, msi::CMD_GET_FAN_STATUS)?;
+    let u16_at = |i: usize| u16::from_le_bytes([reply[i], reply[i + 1]]);
+    Ok(msi::FAN_NAMES
+        .iter()
+        .zip(msi::FAN_RPM_OFFSETS.iter().zip(msi::FAN_DUTY_OFFSETS))
+        .map(|(&name, (&rpm, &duty))| MsiFanReading {
+            name,
+            rpm: u16_at(rpm),
+            duty_pct: u16_at(duty),
+        })
+        .collect())
+}
 
-    for &offset in msi::LED_OFFSETS {
-        if offset < msi::MAX_DATA_LEN {
-            buf[offset] = msi::LED_MODE_DISABLE;
-        }
-    }
-    device
-        .send_feature_report(&buf)
-        .context("Failed to send feature report")?;
-    println!("  MSI CORELIQUID: LEDs disabled");
+/// Raw fan mode byte of the first fan from the fan config readback
+fn msi_read_fan_mode_byte(device: &HidDevice) -> Result<u8> {
+    let reply = msi_request(device, msi::CMD_GET_FAN_CONFIG)?;
+    Ok(reply[msi::FAN_MODE_OFFSETS[0]])
+}
 
-    // Disable LCD
-    let mut cmd = [0u8; msi::HID_REPORT_LEN];
-    cmd[0] = msi::CMD_PREFIX;
-    cmd[1] = msi::CMD_LCD_DISABLE;
-    device.write(&cmd).context("Failed to disable LCD")?;
-    println!("  MSI CORELIQUID: LCD disabled");
+/// Current fan mode, or a protocol error for a mode byte FanMode doesn't know
+fn msi_read_fan_mode(device: &HidDevice) -> Result<FanMode> {
+    let b = msi_read_fan_mode_byte(device)?;
+    FanMode::from_byte(b).ok_or_else(|| {
+        LightsOutError::Protocol(anyhow!("Unknown fan mode byte 0x{:02X}", b)).into()
+    })
+}
 
-    Ok(())
+/// Pump duty for a pump speed percentage, where 0% is the slowest safe duty
+fn msi_pump_duty(pct: u8) -> u8 {
+    let span = (100 - msi::PUMP_DUTY_MIN) as u32;
+    msi::PUMP_DUTY_MIN + (pct.min(100) as u32 * span / 100) as u8
 }
 
-fn msi_set_fan_mode(mode: FanMode) -> Result<()> {
-    let api = HidApi::new().context("Failed to initialize HID API")?;
-    let device = api
-        .open(msi::VID, msi::PID)
-        .context("Failed to open MSI CORELIQUID")?;
+/// Run the pump at a fixed speed (0% = minimum, not off) with a flat curve
+fn msi_set_pump_speed(device: &HidDevice, pct: u8) -> Result<()> {
+    let duty = msi_pump_duty(pct);
+    msi_update_pump_curve(device, |curve| curve.fill(duty))
+}
 
-    let mode_val = mode as u8;
+/// Raise every point of the pump curve to at least `pct`
+fn msi_set_pump_floor(device: &HidDevice, pct: u8) -> Result<()> {
+    let duty = msi_pump_duty(pct);
+    msi_update_pump_curve(device, |curve| {
+        for point in curve {
+            *point = (*point).max(duty);
+        }
+    })
+}
 
-    // Build command buffer with mode at specific offsets
-    let mut buf = [0u8; msi::HID_REPORT_LEN];
-    buf[0] = msi::CMD_PREFIX;
-    buf[1] = msi::CMD_FAN_MODE_1;
-    for &offset in msi::FAN_MODE_OFFSETS {
-        buf[offset] = mode_val;
-    }
+/// Switch the pump channel to the customize mode and edit its duty curve
+///
+/// The fan channels and the pump's temperature points are written back as read
+/// from the cooler, so only the pump changes.
+fn msi_update_pump_curve(device: &HidDevice, update: impl FnOnce(&mut [u8])) -> Result<()> {
+    let offset = msi::FAN_MODE_OFFSETS[msi::PUMP_CHANNEL];
 
-    // Send first command (0x40)
+    let mut buf = msi_request(device, msi::CMD_GET_FAN_CONFIG)?;
+    buf[1] = msi::CMD_FAN_MODE_1;
+    buf[offset] = msi::FAN_MODE_CUSTOMIZE;
+    update(&mut buf[offset + 1..=offset + msi::FAN_CURVE_POINTS]);
     device
         .write(&buf)
         .context("Failed to write fan mode command 0x40")?;
 
-    // Send second command (0x41)
+    let mut buf = msi_request(device, msi::CMD_GET_FAN_TEMP_CONFIG)?;
     buf[1] = msi::CMD_FAN_MODE_2;
+    buf[offset] = msi::FAN_MODE_CUSTOMIZE;
     device
         .write(&buf)
         .context("Failed to write fan mode command 0x41")?;
+    Ok(())
+}
 
-    println!("  MSI CORELIQUID: Fan mode set to {:?}", mode);
+fn msi_pump_speed_set(pct: u8) -> Result<()> {
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = msi_open(&api)?;
+
+    msi_set_pump_speed(&device, pct)?;
+
+    println!(
+        "  MSI CORELIQUID: Pump speed set to {}% ({}% duty)",
+        pct,
+        msi_pump_duty(pct)
+    );
     Ok(())
 }
 
-/// Find the CPU temperature sensor in /sys/class/hwmon
-/// Looks for k10temp (AMD) or coretemp (Intel) chips
-fn find_cpu_temp_path() -> Result<std::path::PathBuf> {
-    let hwmon_path = Path::new("/sys/class/hwmon");
+fn msi_fan_speed_multiplier(multiplier: Option<f32>) -> Result<()> {
+    let mut config = Config::load()?;
+    if let Some(multiplier) = multiplier {
+        config.msi.fan_speed_multiplier = (multiplier != 1.0).then_some(multiplier);
+        config.save()?;
+        println!(
+            "Saved to {} (restart the daemon to apply)",
+            Config::path().display()
+        );
+    }
+    match config.msi.fan_speed_multiplier {
+        Some(multiplier) => println!(
+            "  MSI CORELIQUID: Smart mode temperatures are sent x{}",
+            multiplier
+        ),
+        None => println!("  MSI CORELIQUID: Smart mode gets the real temperature (x1)"),
+    }
+    Ok(())
+}
 
-    for entry in fs::read_dir(hwmon_path).context("Failed to read /sys/class/hwmon")? {
-        let entry = entry?;
-        let name_path = entry.path().join("name");
+/// Put each fan mode on in turn, restoring the original fan configuration afterwards
+///
+/// The fan configuration replies (0x32/0x33) are written back as 0x40/0x41, so
+/// customized curves survive too.
+fn msi_fan_mode_cycle(dwell: Option<Duration>) -> Result<()> {
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = msi_open(&api)?;
+    let fan_config = msi_request(&device, msi::CMD_GET_FAN_CONFIG)?;
+    let temp_config = msi_request(&device, msi::CMD_GET_FAN_TEMP_CONFIG)?;
 
-        if let Ok(name) = fs::read_to_string(&name_path) {
-            let name = name.trim();
-            // AMD CPUs use k10temp, Intel uses coretemp
-            if name == "k10temp" || name == "coretemp" {
-                // For k10temp, Tctl is usually temp1_input
-                // For coretemp, package temp is also temp1_input
-                let temp_path = entry.path().join("temp1_input");
-                if temp_path.exists() {
-                    return Ok(temp_path);
+    // As in led-test, Ctrl+C is only caught in automatic mode; the interactive
+    // prompt accepts "q" instead.
+    let stop_flag = match dwell {
+        Some(_) => install_stop_handler()?,
+        None => Arc::new(AtomicBool::new(false)),
+    };
+
+    let result = (|| -> Result<()> {
+        for &mode in FanMode::value_variants() {
+            if stop_flag.load(Ordering::Relaxed) {
+                break;
+            }
+            msi_write_fan_mode(&device, mode)?;
+            if !wait_for_next_step(dwell, &stop_flag, &format!("Fan mode {:?}", mode))? {
+                break;
+            }
+            match msi_read_fan_status(&device) {
+                Ok(fans) => {
+                    for fan in fans {
+                        println!("    {}: {} rpm ({}%)", fan.name, fan.rpm, fan.duty_pct);
+                    }
                 }
+                Err(e) => println!("    Fan speeds unavailable: {}", e),
             }
         }
+        Ok(())
+    })();
+
+    for (cmd, mut buf) in [
+        (msi::CMD_FAN_MODE_1, fan_config),
+        (msi::CMD_FAN_MODE_2, temp_config),
+    ] {
+        buf[1] = cmd;
+        device
+            .write(&buf)
+            .with_context(|| format!("Failed to restore fan configuration (0x{:02X})", cmd))?;
     }
+    println!("  MSI CORELIQUID: Original fan mode restored");
+    result
+}
 
-    anyhow::bail!("CPU temperature sensor not found (looking for k10temp or coretemp)")
+/// Temperature to send for Smart mode with the configured speed multiplier
+///
+/// Stands in for a Smart mode aggressiveness setting, which the cooler lacks
+/// (see `msi fan speed-multiplier` for the trade-offs).
+fn msi_scaled_temp(temp: i32, multiplier: Option<f32>) -> i32 {
+    match multiplier {
+        Some(multiplier) => (temp as f32 * multiplier).round() as i32,
+        None => temp,
+    }
 }
 
-/// Read CPU temperature in degrees Celsius
-fn read_cpu_temp(temp_path: &Path) -> Result<i32> {
-    let content = fs::read_to_string(temp_path).context("Failed to read temperature")?;
-    let millidegrees: i32 = content.trim().parse().context("Failed to parse temperature")?;
-    Ok(millidegrees / 1000)
+fn msi_fan_read() -> Result<()> {
+    let api = HidApi::new().context("Failed to initialize HID API")?;
+    let device = msi_open(&api)?;
+
+    match msi_read_fan_mode(&device) {
+        Ok(mode) => {
+            let name = mode.to_possible_value().map(|v| v.get_name().to_string());
+            println!("  MSI CORELIQUID: Fan mode {}", name.unwrap_or_default());
+        }
+        Err(_) => {
+            let b = msi_read_fan_mode_byte(&device)?;
+            println!(
+                "  MSI CORELIQUID: Fan mode {} (0x{:02X}) is not a known mode",
+                b, b
+            );
+        }
+    }
+    Ok(())
 }
 
-/// Send CPU temperature to the AIO
-fn send_cpu_temp(device: &HidDevice, temp: i32) -> Result<()> {
-    let mut buf = [0u8; msi::HID_REPORT_LEN];
-    buf[0] = msi::CMD_PREFIX;
-    buf[1] = msi::CMD_CPU_STATUS;
+#[derive(Debug, Default, Serialize)]
+struct SystemStatus {
+    cpu_temp_c: Option<i32>,
+    msi: MsiStatus,
+    lianli: LianliStatus,
+    gpu: GpuStatus,
+}
 
-    // Dummy CPU frequency (the AIO doesn't actually use this)
-    let freq: u16 = 3000;
-    buf[2] = (freq & 0xFF) as u8;
-    buf[3] = ((freq >> 8) & 0xFF) as u8;
+#[derive(Debug, Default, Serialize)]
+struct MsiStatus {
+    connected: bool,
+    error: Option<String>,
+    fan_mode: Option<String>,
+    fans: Vec<MsiFanReading>,
+    zones: Vec<MsiZoneStatus>,
+}
 
-    // CPU temperature (little-endian)
-    buf[4] = (temp & 0xFF) as u8;
-    buf[5] = ((temp >> 8) & 0xFF) as u8;
+#[derive(Debug, Serialize)]
+struct MsiZoneStatus {
+    zone: usize,
+    mode: String,
+    color: String,
+}
 
-    device.write(&buf).context("Failed to send CPU temperature")?;
-    Ok(())
+#[derive(Debug, Default, Serialize)]
+struct LianliStatus {
+    connected: bool,
+    error: Option<String>,
+    leds: Option<String>,
 }
 
-/// Run the temperature monitoring daemon
-fn msi_daemon(stop_flag: Arc<AtomicBool>) -> Result<()> {
-    let api = HidApi::new().context("Failed to initialize HID API")?;
-    let device = api
-        .open(msi::VID, msi::PID)
-        .context("Failed to open MSI CORELIQUID")?;
+#[derive(Debug, Default, Serialize)]
+struct GpuStatus {
+    connected: bool,
+    error: Option<String>,
+    bus: Option<String>,
+    controller: Option<String>,
+    mode: Option<String>,
+    color: Option<String>,
+}
 
-    // Find the CPU temperature sensor
-    let temp_path = find_cpu_temp_path()?;
-    println!("  Found CPU temp sensor: {}", temp_path.display());
-    println!("  Starting temperature monitoring (Ctrl+C to stop)...");
+/// Time `status --all` waits for each device before reporting it disconnected
+const STATUS_DEVICE_TIMEOUT: Duration = Duration::from_secs(2);
 
-    // Main loop
-    while !stop_flag.load(Ordering::Relaxed) {
-        match read_cpu_temp(&temp_path) {
-            Ok(temp) => {
-                println!("  CPU Temperature: {}°C", temp);
-                if let Err(e) = send_cpu_temp(&device, temp) {
-                    eprintln!("  Warning: Failed to send temperature: {}", e);
+/// Read back everything we can; failures are recorded per field, never fatal
+fn collect_status() -> SystemStatus {
+    SystemStatus {
+        cpu_temp_c: collect_cpu_temp(),
+        msi: collect_msi_status(),
+        lianli: collect_lianli_status(),
+        gpu: collect_gpu_status(),
+    }
+}
+
+/// Like `collect_status`, but query the devices concurrently
+///
+/// Each query runs on its own detached thread; one that hasn't answered within
+/// `STATUS_DEVICE_TIMEOUT` is reported as disconnected and left to finish on
+/// its own, so a hung device can't hold up the report.
+fn collect_status_parallel() -> SystemStatus {
+    let cpu_temp = spawn_status_query(collect_cpu_temp);
+    let msi = spawn_status_query(collect_msi_status);
+    let lianli = spawn_status_query(collect_lianli_status);
+    let gpu = spawn_status_query(collect_gpu_status);
+
+    let deadline = Instant::now() + STATUS_DEVICE_TIMEOUT;
+    let remaining = || deadline.saturating_duration_since(Instant::now());
+    let timed_out = || {
+        Some(format!(
+            "timed out after {}s",
+            STATUS_DEVICE_TIMEOUT.as_secs()
+        ))
+    };
+    SystemStatus {
+        cpu_temp_c: cpu_temp.recv_timeout(remaining()).ok().flatten(),
+        msi: msi.recv_timeout(remaining()).unwrap_or_else(|_| MsiStatus {
+            error: timed_out(),
+            ..Default::default()
+        }),
+        lianli: lianli
+            .recv_timeout(remaining())
+            .unwrap_or_else(|_| LianliStatus {
+                error: timed_out(),
+                ..Default::default()
+            }),
+        gpu: gpu.recv_timeout(remaining()).unwrap_or_else(|_| GpuStatus {
+            error: timed_out(),
+            ..Default::default()
+        }),
+    }
+}
+
+/// Run a status query on a background thread and return where its result arrives
+fn spawn_status_query<T: Send + 'static>(
+    query: impl FnOnce() -> T + Send + 'static,
+) -> std::sync::mpsc::Receiver<T> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(query());
+    });
+    rx
+}
+
+fn collect_cpu_temp() -> Option<i32> {
+    find_cpu_temp_path(false)
+        .and_then(|path| read_cpu_temp(&path))
+        .ok()
+}
+
+fn collect_msi_status() -> MsiStatus {
+    let mut status = MsiStatus::default();
+    let device = match HidApi::new() {
+        Ok(api) => msi_open(&api),
+        Err(e) => Err(anyhow!("Failed to initialize HID API: {}", e)),
+    };
+    match device {
+        Ok(device) => {
+            status.connected = true;
+            status.fan_mode = Some(match msi_read_fan_mode_byte(&device) {
+                Ok(b) => FanMode::from_byte(b)
+                    .map_or_else(|| format!("unknown ({})", b), |m| format!("{:?}", m)),
+                Err(e) => format!("unavailable ({})", e),
+            });
+            status.fans = msi_read_fan_status(&device).unwrap_or_default();
+            match msi_read_led_report(&device) {
+                Ok(buf) => {
+                    status.zones = msi::LED_OFFSETS
+                        .iter()
+                        .enumerate()
+                        .map(|(zone, &offset)| MsiZoneStatus {
+                            zone,
+                            mode: msi_led_mode_name(buf[offset]),
+                            color: Rgb::new(
+                                buf[offset + msi::ZONE_COLOR],
+                                buf[offset + msi::ZONE_COLOR + 1],
+                                buf[offset + msi::ZONE_COLOR + 2],
+                            )
+                            .to_string(),
+                        })
+                        .collect()
                 }
-            }
-            Err(e) => {
-                eprintln!("  Warning: Failed to read temperature: {}", e);
+                Err(e) => status.error = Some(format!("{:#}", e)),
             }
         }
+        Err(e) => status.error = Some(format!("{:#}", e)),
+    }
+    status
+}
 
-        // Sleep for the interval, checking stop flag periodically
-        for _ in 0..(msi::DAEMON_INTERVAL_SECS * 10) {
-            if stop_flag.load(Ordering::Relaxed) {
-                break;
-            }
-            std::thread::sleep(Duration::from_millis(100));
+fn collect_lianli_status() -> LianliStatus {
+    let mut status = LianliStatus::default();
+    let device = match HidApi::new() {
+        Ok(api) => lianli_open(&api),
+        Err(e) => Err(anyhow!("Failed to initialize HID API: {}", e)),
+    };
+    match device {
+        Ok(_) => {
+            status.connected = true;
+            status.leds = Some("(write-only)".to_string());
         }
+        Err(e) => status.error = Some(format!("{:#}", e)),
     }
+    status
+}
 
-    println!("  Daemon stopped.");
-    Ok(())
+fn collect_gpu_status() -> GpuStatus {
+    let mut status = GpuStatus::default();
+    match find_gpu_i2c_bus() {
+        Ok(bus) => {
+            match gpu_open_bus(&bus) {
+                Ok(mut device) => match ene_read_device_name(&mut device) {
+                    Ok(name) => {
+                        status.connected = true;
+                        status.controller = Some(name);
+                        status.mode = Some(match ene_read(&mut device, gpu::ENE_REG_MODE) {
+                            Ok(mode) => format!("{}", mode),
+                            Err(_) => "(write-only)".to_string(),
+                        });
+                        status.color = gpu_read_color(&mut device)
+                            .ok()
+                            .or_else(|| gpu_cached_color().ok().flatten())
+                            .map(|rgb| rgb.to_string());
+                    }
+                    Err(e) => status.error = Some(format!("{:#}", e)),
+                },
+                Err(e) => status.error = Some(format!("{:#}", e)),
+            }
+            status.bus = Some(bus);
+        }
+        Err(e) => status.error = Some(format!("{:#}", e)),
+    }
+    status
 }
 
-fn lianli_disable() -> Result<()> {
-    let api = HidApi::new().context("Failed to initialize HID API")?;
+fn print_status(status: &SystemStatus) {
+    let connection = |connected: bool, error: &Option<String>| match (connected, error) {
+        (true, _) => "connected".to_string(),
+        (false, Some(e)) => format!("disconnected ({})", e),
+        (false, None) => "disconnected".to_string(),
+    };
 
-    // Find the device by iterating (like uni-sync does)
-    let device_info = api
-        .device_list()
-        .find(|d| d.vendor_id() == lianli::VID && d.product_id() == lianli::PID)
-        .context("LianLi UNI FAN AL V2 not found")?;
+    match status.cpu_temp_c {
+        Some(temp) => println!("CPU temperature: {}°C", temp),
+        None => println!("CPU temperature: unavailable"),
+    }
 
-    let device = api
-        .open_path(device_info.path())
-        .context("Failed to open LianLi UNI FAN AL V2")?;
+    println!(
+        "\nMSI CORELIQUID: {}",
+        connection(status.msi.connected, &status.msi.error)
+    );
+    if status.msi.connected {
+        if let Some(mode) = &status.msi.fan_mode {
+            println!("  Fan mode: {}", mode);
+        }
+        for fan in &status.msi.fans {
+            println!("  {}: {} rpm ({}%)", fan.name, fan.rpm, fan.duty_pct);
+        }
+        for zone in &status.msi.zones {
+            println!("  LED zone {:2}: {} ({})", zone.zone, zone.mode, zone.color);
+        }
+    }
 
-    // Following OpenRGB LianLiUniHubALController protocol:
-    // 1. Send color data (all black) - 146 byte packet
-    // 2. Send commit action with 0% brightness - 65 byte packet
+    println!(
+        "\nLianLi UNI FAN AL V2: {}",
+        connection(status.lianli.connected, &status.lianli.error)
+    );
+    if let Some(leds) = &status.lianli.leds {
+        println!("  LEDs: {}", leds);
+    }
+
+    println!(
+        "\nGPU (ENE): {}",
+        connection(status.gpu.connected, &status.gpu.error)
+    );
+    if let Some(bus) = &status.gpu.bus {
+        println!("  i2c bus: {}", bus);
+    }
+    if let Some(controller) = &status.gpu.controller {
+        println!("  Controller: {}", controller);
+    }
+    if let Some(mode) = &status.gpu.mode {
+        println!("  LED mode: {}", mode);
+    }
+    if let Some(color) = &status.gpu.color {
+        println!("  LED color: {}", color);
+    }
+}
 
-    for channel in 0..lianli::NUM_CHANNELS {
-        // Send black color data for fan LEDs (register 0x30 + channel*2)
-        let mut color_packet = [0u8; lianli::COLOR_PACKET_SIZE];
-        color_packet[0] = lianli::TRANSACTION_ID;
-        color_packet[1] = 0x30 + (channel * 2); // Fan LEDs
-        // Rest is zeros (black RGB)
-        match device.write(&color_packet) {
-            Ok(_) => {}
-            Err(e) => eprintln!("    Warning: color packet ch{} fan failed: {}", channel, e),
+fn status(json: bool, watch: bool, all: bool) -> Result<()> {
+    loop {
+        let status = if all {
+            collect_status_parallel()
+        } else {
+            collect_status()
+        };
+        if watch {
+            // Clear the screen and redraw in place
+            print!("\x1b[2J\x1b[H");
+        }
+        if json {
+            println!("{}", serde_json::to_string_pretty(&status)?);
+        } else {
+            print_status(&status);
         }
-        std::thread::sleep(std::time::Duration::from_millis(20));
+        if !watch {
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_secs(1));
+    }
+}
 
-        // Send black color data for edge LEDs (register 0x31 + channel*2)
-        color_packet[1] = 0x31 + (channel * 2); // Edge LEDs
-        match device.write(&color_packet) {
-            Ok(_) => {}
-            Err(e) => eprintln!("    Warning: color packet ch{} edge failed: {}", channel, e),
+fn print_hex_dump(buf: &[u8]) {
+    for (i, chunk) in buf.chunks(16).enumerate() {
+        print!("{:04x}: ", i * 16);
+        for b in chunk {
+            print!("{:02x} ", b);
         }
-        std::thread::sleep(std::time::Duration::from_millis(20));
+        println!();
+    }
+}
 
-        // Commit action for fan LEDs - 65 byte packet
-        let mut commit = [0u8; lianli::PACKET_SIZE];
-        commit[0] = lianli::TRANSACTION_ID;
-        commit[1] = 0x10 + (channel * 2); // Fan LEDs commit register
-        commit[2] = lianli::MODE_STATIC;
-        commit[3] = lianli::SPEED_VERY_SLOW;
-        commit[4] = lianli::DIRECTION_LEFT_TO_RIGHT;
-        commit[5] = lianli::BRIGHTNESS_OFF;
-        device
-            .write(&commit)
-            .context("Failed to write fan LED commit")?;
-        std::thread::sleep(std::time::Duration::from_millis(20));
+/// Name of a byte in the MSI LED feature report
+#[derive(Debug, Clone, Deserialize)]
+struct FieldAnnotation {
+    offset: usize,
+    name: String,
+}
 
-        // Commit action for edge LEDs
-        commit[1] = 0x11 + (channel * 2); // Edge LEDs commit register
-        device
-            .write(&commit)
-            .context("Failed to write edge LED commit")?;
-        std::thread::sleep(std::time::Duration::from_millis(20));
+impl FieldAnnotation {
+    fn new(offset: usize, name: impl Into<String>) -> Self {
+        FieldAnnotation {
+            offset,
+            name: name.into(),
+        }
     }
 
-    println!("  LianLi UNI FAN AL V2: LEDs disabled (static black, 0% brightness)");
-    Ok(())
-}
+    /// File with extra/overriding annotations: `[{"offset": 63, "name": "..."}]`
+    fn user_path() -> std::path::PathBuf {
+        config_dir().join("msi-annotations.json")
+    }
 
-/// Find the AMDGPU OEM i2c bus by scanning /sys/class/i2c-dev/*/name
-fn find_gpu_i2c_bus() -> Result<String> {
-    let i2c_dev_path = Path::new("/sys/class/i2c-dev");
+    /// Built-in annotations from the OpenRGB Mystic Light 185-byte layout,
+    /// followed by any from the user annotation file (which take precedence)
+    fn table() -> Result<Vec<FieldAnnotation>> {
+        let mut table = vec![FieldAnnotation::new(0, "Report ID")];
+        for (zone, (&offset, (name, _))) in msi::LED_OFFSETS.iter().zip(msi::ZONE_MAP).enumerate() {
+            let field = |rel: usize, what: &str| {
+                FieldAnnotation::new(
+                    offset + rel,
+                    format!("LED zone {} ({}) {}", zone, name, what),
+                )
+            };
+            table.push(field(0, "mode"));
+            table.push(field(msi::ZONE_COLOR, "red"));
+            table.push(field(msi::ZONE_COLOR + 1, "green"));
+            table.push(field(msi::ZONE_COLOR + 2, "blue"));
+            table.push(field(msi::ZONE_SPEED_BRIGHTNESS, "speed/brightness"));
+            table.push(field(msi::ZONE_COLOR2, "color 2 red"));
+            table.push(field(msi::ZONE_COLOR2 + 1, "color 2 green"));
+            table.push(field(msi::ZONE_COLOR2 + 2, "color 2 blue"));
+            table.push(field(msi::ZONE_COLOR_FLAGS, "color flags"));
+            table.push(field(msi::ZONE_COLOR_FLAGS + 1, "padding"));
+            if msi::RAINBOW_ZONE_OFFSETS.contains(&offset) {
+                table.push(field(msi::ZONE_LEN, "LED count"));
+            }
+        }
+        table.push(FieldAnnotation::new(msi::SAVE_DATA_OFFSET, "Save to flash"));
 
-    for entry in fs::read_dir(i2c_dev_path).context("Failed to read /sys/class/i2c-dev")? {
-        let entry = entry?;
-        let name_path = entry.path().join("name");
-        if let Ok(name) = fs::read_to_string(&name_path) {
-            // Look for "AMDGPU DM i2c OEM bus" or similar
-            if name.contains("AMDGPU") && name.contains("OEM") {
-                let dev_name = entry.file_name();
-                let bus_path = format!("/dev/{}", dev_name.to_string_lossy());
-                return Ok(bus_path);
+        let path = FieldAnnotation::user_path();
+        match fs::read_to_string(&path) {
+            Ok(content) => {
+                let extra: Vec<FieldAnnotation> = serde_json::from_str(&content)
+                    .with_context(|| format!("Failed to parse {}", path.display()))?;
+                table.extend(extra);
             }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
         }
+        Ok(table)
     }
 
-    anyhow::bail!("AMDGPU OEM i2c bus not found. Ensure kernel >= 6.14 with OEM i2c patches.")
+    /// Name for `offset`; later entries win
+    fn lookup(table: &[FieldAnnotation], offset: usize) -> &str {
+        table
+            .iter()
+            .rev()
+            .find(|a| a.offset == offset)
+            .map_or("(??)", |a| a.name.as_str())
+    }
 }
 
-fn gpu_disable() -> Result<()> {
-    let bus_path = find_gpu_i2c_bus()?;
-    println!("  GPU: Found i2c bus at {}", bus_path);
+fn print_annotated_dump(buf: &[u8], table: &[FieldAnnotation]) {
+    for (offset, b) in buf.iter().enumerate() {
+        println!(
+            "  {:3} (0x{:02x}): {:02x}  {}",
+            offset,
+            offset,
+            b,
+            FieldAnnotation::lookup(table, offset)
+        );
+    }
+}
 
-    let mut device = LinuxI2CDevice::new(&bus_path, gpu::ENE_I2C_ADDR)
-        .context("Failed to open GPU i2c device")?;
+/// Take `count` LED report snapshots and write them as a recording
+///
+/// File layout: the magic, the report length (u32 LE), then per snapshot the
+/// milliseconds since the Unix epoch (u64 LE) followed by the report.
+fn msi_dump_record(device: &HidDevice, count: u32, interval: Duration, out: &Path) -> Result<()> {
+    let stop_flag = install_stop_handler()?;
+    let mut content = msi::RECORD_MAGIC.to_vec();
+    content.extend_from_slice(&(msi::MAX_DATA_LEN as u32).to_le_bytes());
 
-    // Set LED mode to OFF
-    // Write register address (byte-swapped)
-    device
-        .smbus_write_word_data(gpu::SMBUS_CMD_ADDR, gpu::swap_bytes(gpu::ENE_REG_MODE))
-        .context("Failed to write mode register address")?;
-    // Write mode value
-    device
-        .smbus_write_byte_data(gpu::SMBUS_CMD_DATA, gpu::ENE_MODE_OFF)
-        .context("Failed to write mode value")?;
+    let mut taken = 0;
+    while taken < count && !stop_flag.load(Ordering::Relaxed) {
+        if taken > 0 {
+            sleep_unless_stopped(&stop_flag, interval);
+        }
+        let buf = msi_read_led_report(device)?;
+        let millis = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        content.extend_from_slice(&millis.to_le_bytes());
+        content.extend_from_slice(&buf);
+        taken += 1;
+    }
 
-    // Apply changes
-    device
-        .smbus_write_word_data(gpu::SMBUS_CMD_ADDR, gpu::swap_bytes(gpu::ENE_REG_APPLY))
-        .context("Failed to write apply register address")?;
-    device
-        .smbus_write_byte_data(gpu::SMBUS_CMD_DATA, gpu::ENE_APPLY_VAL)
-        .context("Failed to write apply value")?;
+    write_atomic(out, &content)?;
+    println!("Recorded {} snapshot(s) to {}", taken, out.display());
+    Ok(())
+}
 
-    println!("  GPU: LEDs disabled");
+/// Show the snapshots of a recording one per second, redrawing in place
+fn msi_dump_replay(path: &Path) -> Result<()> {
+    let content = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let header_len = msi::RECORD_MAGIC.len() + 4;
+    if content.len() < header_len || !content.starts_with(msi::RECORD_MAGIC) {
+        return Err(LightsOutError::Config(anyhow!(
+            "{} is not a `msi dump --record` file",
+            path.display()
+        ))
+        .into());
+    }
+    let report_len =
+        u32::from_le_bytes(content[header_len - 4..header_len].try_into().unwrap()) as usize;
+    let snapshots: Vec<&[u8]> = content[header_len..].chunks_exact(8 + report_len).collect();
+    let Some(first) = snapshots.first() else {
+        println!("{} holds no snapshots", path.display());
+        return Ok(());
+    };
+
+    let millis = |snapshot: &[u8]| u64::from_le_bytes(snapshot[..8].try_into().unwrap());
+    let start = millis(first);
+    for (i, snapshot) in snapshots.iter().enumerate() {
+        if i > 0 {
+            std::thread::sleep(Duration::from_secs(1));
+        }
+        // Clear the screen and redraw in place
+        print!("\x1b[2J\x1b[H");
+        println!(
+            "Snapshot {}/{} at +{} ms ({} bytes):",
+            i + 1,
+            snapshots.len(),
+            millis(snapshot) - start,
+            report_len
+        );
+        print_hex_dump(&snapshot[8..]);
+    }
     Ok(())
 }
 
-fn msi_dump() -> Result<()> {
+fn msi_dump(args: &DumpArgs) -> Result<()> {
+    if let Some(path) = &args.replay {
+        return msi_dump_replay(path);
+    }
+
     let api = HidApi::new().context("Failed to initialize HID API")?;
-    let device = api
-        .open(msi::VID, msi::PID)
-        .context("Failed to open MSI CORELIQUID")?;
+    let device = msi_open(&api)?;
 
-    let mut buf = [0u8; msi::MAX_DATA_LEN];
-    buf[0] = msi::FEATURE_REPORT_ID;
-    device.get_feature_report(&mut buf)?;
+    if let (Some(count), Some(out)) = (args.record, &args.output) {
+        return msi_dump_record(&device, count, args.interval, out);
+    }
+
+    if args.all_reports {
+        let mut found = 0;
+        for id in 0..=u8::MAX {
+            let mut buf = [0u8; msi::MAX_REPORT_PROBE_LEN];
+            buf[0] = id;
+            // Most IDs are not implemented by the firmware; skip the errors
+            let len = match device.get_feature_report(&mut buf) {
+                Ok(len) if len > 0 => len,
+                _ => continue,
+            };
+            println!("Feature report 0x{:02X} ({} bytes):", id, len);
+            print_hex_dump(&buf[..len]);
+            println!();
+            found += 1;
+        }
+        println!("{} readable feature report(s)", found);
+        return Ok(());
+    }
+
+    if let Some(id) = args.report_id {
+        let mut buf = [0u8; msi::MAX_REPORT_PROBE_LEN];
+        buf[0] = id;
+        let len = device
+            .get_feature_report(&mut buf)
+            .with_context(|| format!("Failed to get feature report 0x{:02X}", id))?;
+        println!("Feature report 0x{:02X} ({} bytes):", id, len);
+        print_hex_dump(&buf[..len]);
+        return Ok(());
+    }
+
+    let buf = msi_read_led_report(&device)?;
+
+    if args.save_baseline {
+        let path = args.baseline_path();
+        write_atomic(&path, &buf)?;
+        println!("Saved LED report baseline to {}", path.display());
+        return Ok(());
+    }
+
+    if args.only_changed {
+        let path = args.baseline_path();
+        let baseline = fs::read(&path).with_context(|| {
+            format!(
+                "Failed to read baseline {} (create it with --save-baseline)",
+                path.display()
+            )
+        })?;
+        let table = FieldAnnotation::table()?;
+        let mut changed = 0;
+        println!("Bytes differing from {}:", path.display());
+        for (offset, &b) in buf.iter().enumerate() {
+            let old = baseline.get(offset).copied();
+            if old != Some(b) {
+                let old = old.map_or("--".to_string(), |o| format!("{:02x}", o));
+                println!(
+                    "  {:3} (0x{:02x}): {} -> {:02x}  {}",
+                    offset,
+                    offset,
+                    old,
+                    b,
+                    FieldAnnotation::lookup(&table, offset)
+                );
+                changed += 1;
+            }
+        }
+        println!("{} byte(s) changed", changed);
+        return Ok(());
+    }
 
     println!(
         "Feature report 0x{:02X} ({} bytes):",
         msi::FEATURE_REPORT_ID,
         msi::MAX_DATA_LEN
     );
-    for (i, chunk) in buf.chunks(16).enumerate() {
-        print!("{:04x}: ", i * 16);
-        for b in chunk {
-            print!("{:02x} ", b);
-        }
-        println!();
+    if args.annotate {
+        print_annotated_dump(&buf, &FieldAnnotation::table()?);
+        return Ok(());
     }
+    print_hex_dump(&buf);
 
     println!("\nLED area modes:");
     for &offset in msi::LED_OFFSETS {
@@ -418,14 +7209,39 @@ fn msi_dump() -> Result<()> {
     Ok(())
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            // --help/--version are not errors; usage errors share the config exit code
+            return if e.use_stderr() {
+                ExitCode::from(5)
+            } else {
+                ExitCode::SUCCESS
+            };
+        }
+    };
+
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            let err = LightsOutError::classify(e);
+            eprintln!("Error: {:?}", err.inner());
+            ExitCode::from(err.exit_code())
+        }
+    }
+}
 
+fn run(cli: Cli) -> Result<()> {
+    if let Commands::Msi { device_index, .. } = cli.command {
+        let _ = MSI_DEVICE_INDEX.set(device_index);
+    }
     match cli.command {
         Commands::Off => {
             println!("Disabling all RGB LEDs...\n");
 
-            if let Err(e) = msi_disable() {
+            if let Err(e) = msi_disable(cli.verbose) {
                 println!("  MSI CORELIQUID: not found or error: {}", e);
             }
 
@@ -433,7 +7249,7 @@ fn main() -> Result<()> {
                 println!("  LianLi UNI FAN: not found or error: {}", e);
             }
 
-            if let Err(e) = gpu_disable() {
+            if let Err(e) = gpu_disable(GpuProtocol::Auto) {
                 println!("  GPU: not found or error: {}", e);
             }
 
@@ -445,37 +7261,392 @@ fn main() -> Result<()> {
             println!("\nDone!");
             Ok(())
         }
-        Commands::Msi => {
+        Commands::Msi { command: None, .. } => {
             println!("Disabling MSI CORELIQUID LEDs...");
-            msi_disable()
+            msi_disable(cli.verbose)
         }
-        Commands::Lianli => {
+        Commands::Msi {
+            command: Some(MsiCommands::Dump(args)),
+            ..
+        } => msi_dump(&args),
+        Commands::Msi {
+            command: Some(MsiCommands::HidInfo),
+            ..
+        } => msi_hid_info(),
+        Commands::Msi {
+            command: Some(MsiCommands::Devices),
+            ..
+        } => msi_devices(),
+        Commands::Msi {
+            command: Some(MsiCommands::ReportSizeDetect),
+            ..
+        } => msi_report_size_detect(),
+        Commands::Msi {
+            command:
+                Some(MsiCommands::Fan {
+                    command: MsiFanCommands::Read,
+                }),
+            ..
+        } => msi_fan_read(),
+        Commands::Msi {
+            command:
+                Some(MsiCommands::Fan {
+                    command: MsiFanCommands::SpeedMultiplier { multiplier },
+                }),
+            ..
+        } => msi_fan_speed_multiplier(multiplier),
+        Commands::Msi {
+            command:
+                Some(MsiCommands::Fan {
+                    command: MsiFanCommands::ModeCycle { dwell, interactive },
+                }),
+            ..
+        } => msi_fan_mode_cycle((!interactive).then_some(dwell)),
+        Commands::Msi {
+            command:
+                Some(MsiCommands::PumpSpeed {
+                    command: MsiPumpSpeedCommands::Set { percentage },
+                }),
+            ..
+        } => msi_pump_speed_set(percentage),
+        Commands::Msi {
+            command: Some(MsiCommands::ValidateProtocol),
+            ..
+        } => msi_validate_protocol(),
+        Commands::Msi {
+            command: Some(MsiCommands::LedTest { dwell }),
+            ..
+        } => msi_led_test(dwell),
+        Commands::Msi {
+            command: Some(MsiCommands::LedColormap { colormap }),
+            ..
+        } => msi_led_colormap(&colormap),
+        Commands::Msi {
+            command: Some(MsiCommands::LedSync { source }),
+            ..
+        } => msi_led_sync(source),
+        Commands::Msi {
+            command: Some(MsiCommands::LedAll { mode, color }),
+            ..
+        } => msi_led_all(mode, color),
+        Commands::Msi {
+            command: Some(MsiCommands::PumpHead { color }),
+            ..
+        } => msi_set_zone_group(msi::PUMP_HEAD_ZONES, "Pump head", color),
+        Commands::Msi {
+            command: Some(MsiCommands::RadiatorFans { color }),
+            ..
+        } => msi_set_zone_group(msi::RADIATOR_FAN_ZONES, "Radiator fans", color),
+        Commands::Msi {
+            command: Some(MsiCommands::ExportProfile { format, out }),
+            ..
+        } => msi_export_profile(format, &out),
+        Commands::Msi {
+            command: Some(MsiCommands::EepromBackup { out }),
+            ..
+        } => msi_eeprom_backup(&out),
+        Commands::Msi {
+            command: Some(MsiCommands::EepromRestore { input, force }),
+            ..
+        } => msi_eeprom_restore(&input, force),
+        Commands::Msi {
+            command: Some(MsiCommands::FirmwareUpdateCheck { online }),
+            ..
+        } => msi_firmware_update_check(online),
+        Commands::Msi {
+            command: Some(MsiCommands::LedZoneMap),
+            ..
+        } => {
+            msi_led_zone_map();
+            Ok(())
+        }
+        Commands::Msi {
+            command: Some(MsiCommands::ImportProfile { from_openrgb }),
+            ..
+        } => msi_import_profile(&from_openrgb),
+        Commands::Msi {
+            command: Some(MsiCommands::LedMode { zone, mode }),
+            ..
+        } => msi_set_led_mode(zone, mode),
+        Commands::Msi {
+            command: Some(MsiCommands::LedPreset { profile }),
+            ..
+        } => msi_apply_led_preset(profile),
+        Commands::Msi {
+            command:
+                Some(MsiCommands::WriteRaw {
+                    bytes,
+                    feature_report,
+                    read_after,
+                }),
+            ..
+        } => msi_write_raw(&bytes, feature_report, read_after),
+        Commands::Msi {
+            command:
+                Some(MsiCommands::TestTempSend {
+                    value,
+                    count,
+                    interval,
+                }),
+            ..
+        } => msi_test_temp_send(value, count, Duration::from_millis(interval), cli.verbose),
+        Commands::Msi {
+            command: Some(MsiCommands::TempSimulate { simulation, daemon }),
+            ..
+        } => {
+            let simulation = TempSimulation::from_args(&simulation)?;
+            println!("Starting MSI CORELIQUID temperature daemon (simulated temperature)...");
+            run_daemon(&daemon, Some(simulation))
+        }
+        Commands::Lianli { command: None } => {
             println!("Disabling LianLi UNI FAN AL V2 LEDs...");
             lianli_disable()
         }
-        Commands::Gpu => {
+        Commands::Lianli {
+            command: Some(LianliCommands::PortMap { channels, fans }),
+        } => lianli_port_map(&channels, &fans),
+        Commands::Lianli {
+            command: Some(LianliCommands::Speed { channel, speed }),
+        } => lianli_set_speed(channel, speed),
+        Commands::Lianli {
+            command: Some(LianliCommands::SaveState { output }),
+        } => lianli_save_state(output.as_deref()),
+        Commands::Lianli {
+            command: Some(LianliCommands::LoadState { input }),
+        } => lianli_load_state(input.as_deref()),
+        Commands::Lianli {
+            command: Some(LianliCommands::EffectChain { spec }),
+        } => lianli_effect_chain(&spec),
+        Commands::Lianli {
+            command: Some(LianliCommands::HubPassthrough { packets, padding }),
+        } => lianli_hub_passthrough(&packets, padding),
+        Commands::Lianli {
+            command: Some(LianliCommands::ControllerInfo { json }),
+        } => lianli_controller_info(json),
+        Commands::Lianli {
+            command: Some(LianliCommands::HexDump { kind, channel }),
+        } => lianli_hex_dump(kind, channel),
+        Commands::Lianli {
+            command: Some(LianliCommands::DirectMode { fps }),
+        } => lianli_direct_mode(fps),
+        Commands::Lianli {
+            command: Some(LianliCommands::TestPattern { channel, dwell }),
+        } => lianli_test_pattern(channel, dwell),
+        Commands::Lianli {
+            command: Some(LianliCommands::ColorZones { fan, edge }),
+        } => lianli_color_zones(fan, edge),
+        Commands::Lianli {
+            command: Some(LianliCommands::ColorTemp { kelvin, brightness }),
+        } => lianli_color_temp(kelvin, brightness),
+        Commands::Lianli {
+            command:
+                Some(LianliCommands::ColorWheel {
+                    channel,
+                    position,
+                    saturation,
+                    value,
+                }),
+        } => lianli_color_wheel(channel, position, saturation, value),
+        Commands::Lianli {
+            command:
+                Some(LianliCommands::PerChannelEffect {
+                    channels,
+                    effects,
+                    colors,
+                }),
+        } => lianli_per_channel_effect(&channels, &effects, &colors),
+        Commands::Lianli {
+            command: Some(LianliCommands::ColorScene { scene }),
+        } => lianli_color_scene(scene),
+        Commands::Lianli {
+            command: Some(LianliCommands::ColorFromImage { path, zones }),
+        } => lianli_color_from_image(&path, zones),
+        Commands::Lianli {
+            command: Some(LianliCommands::FanTest { duration }),
+        } => lianli_fan_test(duration),
+        Commands::Lianli {
+            command: Some(LianliCommands::Identify { channel, duration }),
+        } => lianli_identify(channel, duration),
+        Commands::Lianli {
+            command: Some(LianliCommands::ColorAnimation { kind, speed, color }),
+        } => lianli_color_animation(kind, speed, color),
+        Commands::Gpu {
+            command: None,
+            allow_fallback,
+            protocol,
+        } => {
             println!("Disabling GPU LEDs...");
-            gpu_disable()
+            gpu_disable_with_fallback(protocol, allow_fallback)
         }
+        Commands::Gpu {
+            command: Some(GpuCommands::AutoDetectEne),
+            ..
+        } => gpu_auto_detect_ene(),
+        Commands::Gpu {
+            command: Some(GpuCommands::CheckPermissions),
+            ..
+        } => gpu_check_permissions(),
+        Commands::Gpu {
+            command: Some(GpuCommands::I2cHealthCheck { count }),
+            ..
+        } => gpu_i2c_health_check(count),
+        Commands::Gpu {
+            command: Some(GpuCommands::Enable { mode, rgb }),
+            ..
+        } => gpu_enable(mode, rgb),
+        Commands::Gpu {
+            command: Some(GpuCommands::SetMode { mode, rgb }),
+            ..
+        } => gpu_set_mode(mode, rgb),
+        Commands::Gpu {
+            command: Some(GpuCommands::FallbackViaSysfs { path }),
+            ..
+        } => gpu_fallback_via_sysfs(path.as_deref()),
+        Commands::Gpu {
+            command: Some(GpuCommands::MonitorI2cTraffic { bus, duration }),
+            ..
+        } => gpu_monitor_i2c_traffic(bus, duration),
+        Commands::Gpu {
+            command: Some(GpuCommands::EnumerateSlaves { bus, yes }),
+            ..
+        } => gpu_enumerate_slaves(bus, yes),
+        Commands::Gpu {
+            command: Some(GpuCommands::EneDump { watch }),
+            ..
+        } => gpu_ene_dump(watch),
+        Commands::Gpu {
+            command: Some(GpuCommands::ReadColor),
+            ..
+        } => gpu_print_color(),
+        Commands::Gpu {
+            command: Some(GpuCommands::TestWriteSequence),
+            ..
+        } => gpu_open().and_then(|mut device| gpu_test_write_sequence(&mut device, cli.verbose)),
+        Commands::Gpu {
+            command:
+                Some(GpuCommands::DisableOnBoot {
+                    command: DisableOnBootCommands::Install,
+                }),
+            ..
+        } => gpu_disable_on_boot_install(),
+        Commands::Gpu {
+            command:
+                Some(GpuCommands::DisableOnBoot {
+                    command: DisableOnBootCommands::Uninstall,
+                }),
+            ..
+        } => gpu_disable_on_boot_uninstall(),
         Commands::Fan { mode } => {
             println!("Setting MSI CORELIQUID fan mode...");
             msi_set_fan_mode(mode)
         }
-        Commands::Daemon => {
+        Commands::Daemon {
+            command: Some(DaemonCommands::Stop),
+            args,
+        } => daemon_stop(&args.pid_file.unwrap_or_else(daemon_pid_path)),
+        Commands::Daemon {
+            command: None,
+            args,
+        } => {
             println!("Starting MSI CORELIQUID temperature daemon...");
+            run_daemon(&args, None)
+        }
+        Commands::Dump(args) => msi_dump(&args),
+        Commands::Profile {
+            command:
+                ProfileCommands::Apply {
+                    profile,
+                    transition,
+                    duration,
+                },
+        } => profile_apply(profile, transition, Duration::from_millis(duration)),
+        Commands::Status { json, watch, all } => status(json, watch, all),
+    }
+}
 
-            // Set up signal handler for graceful shutdown
-            let stop_flag = Arc::new(AtomicBool::new(false));
-            let stop_flag_clone = stop_flag.clone();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            ctrlc::set_handler(move || {
-                println!("\n  Received shutdown signal...");
-                stop_flag_clone.store(true, Ordering::Relaxed);
-            })
-            .context("Failed to set signal handler")?;
+    // Lines in the tracefs format of the smbus_write/smbus_read events
+    const SELECT_MODE: &str = "  i2cset-1234  [002] .....  101.000001: smbus_write: i2c-3 a=067 \
+                               f=0000 c=0 WORD_DATA l=2 [80-21]";
+    const WRITE_OFF: &str = "  i2cset-1234  [002] .....  101.000002: smbus_write: i2c-3 a=067 \
+                             f=0000 c=1 BYTE_DATA l=1 [00]";
+    const READ: &str = "  i2cget-1235  [002] .....  101.000003: smbus_read: i2c-3 a=067 \
+                        f=0000 c=81 BYTE_DATA";
 
-            msi_daemon(stop_flag)
-        }
-        Commands::Dump => msi_dump(),
+    #[test]
+    fn ene_trace_select_is_big_endian() {
+        let mut decoder = EneTraceDecoder::default();
+        assert_eq!(
+            decoder.decode(SELECT_MODE).as_deref(),
+            Some("select 0x8021 (mode)")
+        );
+        assert_eq!(decoder.register, Some(gpu::ENE_REG_MODE));
+    }
+
+    #[test]
+    fn ene_trace_data_write_uses_selected_register() {
+        let mut decoder = EneTraceDecoder::default();
+        // Without a select there is nothing to attribute the write to
+        assert_eq!(decoder.decode(WRITE_OFF), None);
+        decoder.decode(SELECT_MODE);
+        assert_eq!(
+            decoder.decode(WRITE_OFF).as_deref(),
+            Some("0x8021 (mode) = 0x00")
+        );
+    }
+
+    #[test]
+    fn ene_trace_read_uses_selected_register() {
+        let mut decoder = EneTraceDecoder::default();
+        decoder.decode(SELECT_MODE);
+        assert_eq!(decoder.decode(READ).as_deref(), Some("read 0x8021 (mode)"));
+    }
+
+    #[test]
+    fn ene_trace_ignores_other_devices() {
+        let mut decoder = EneTraceDecoder::default();
+        let other = SELECT_MODE.replace("a=067", "a=050");
+        assert_eq!(decoder.decode(&other), None);
+        assert_eq!(decoder.register, None);
+        assert_eq!(
+            decoder.decode("  kworker/0:1-42  [000] .....  99.0: i2c_result: i2c-3 n=1 ret=1"),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_duration_units() {
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration("2").unwrap(), Duration::from_secs(2));
+        assert_eq!(
+            parse_duration(" 1.5s ").unwrap(),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(parse_duration("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn parse_duration_rejects_bad_input() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("5d").is_err());
+        assert!(parse_duration("-1s").is_err());
+        // Too large for a Duration: an error, not a panic
+        assert!(parse_duration("99999999999999999999h").is_err());
+    }
+
+    #[test]
+    fn exec_arg_quoting() {
+        assert_eq!(
+            systemd::quote_exec_arg("/opt/my tools/lights-out"),
+            "\"/opt/my tools/lights-out\""
+        );
+        assert_eq!(
+            systemd::quote_exec_arg("/a\\b\"c%d$e"),
+            "\"/a\\\\b\\\"c%%d$$e\""
+        );
     }
 }
@@ -0,0 +1,235 @@
+// Daemon metrics: Prometheus endpoint (`daemon --metrics-server`) and
+// InfluxDB push (`daemon --export-metrics influxdb`)
+
+use anyhow::{anyhow, Context, Result};
+use std::fmt::Write;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Timeout for one InfluxDB write, so a dead server can't back up the queue for long
+const INFLUX_TIMEOUT_SECS: u64 = 5;
+/// Points waiting for the writer thread; newer ones are dropped while it's full
+const INFLUX_QUEUE_LEN: usize = 8;
+/// Minimum time between repeated InfluxDB failure warnings
+const INFLUX_WARN_INTERVAL_SECS: u64 = 60;
+
+/// Latest daemon readings, shared between the daemon loop and the HTTP server
+pub struct MetricsSnapshot {
+    pub started: Instant,
+    pub cpu_temp_celsius: Option<i32>,
+    /// (fan name, RPM) from the last fan status readback
+    pub fan_rpm: Vec<(&'static str, u16)>,
+    pub temp_read_failures: u64,
+}
+
+impl MetricsSnapshot {
+    pub fn new() -> MetricsSnapshot {
+        MetricsSnapshot {
+            started: Instant::now(),
+            cpu_temp_celsius: None,
+            fan_rpm: Vec::new(),
+            temp_read_failures: 0,
+        }
+    }
+
+    /// Render in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, samples: &[(String, String)]| {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+            for (labels, value) in samples {
+                let _ = writeln!(out, "{}{} {}", name, labels, value);
+            }
+        };
+
+        if let Some(temp) = self.cpu_temp_celsius {
+            metric(
+                "lights_out_cpu_temp_celsius",
+                "gauge",
+                "CPU temperature sent to the AIO",
+                &[(String::new(), temp.to_string())],
+            );
+        }
+        if let Some(&(_, rpm)) = self.fan_rpm.iter().find(|(name, _)| *name == "Pump") {
+            metric(
+                "lights_out_pump_rpm",
+                "gauge",
+                "AIO pump speed",
+                &[(String::new(), rpm.to_string())],
+            );
+        }
+        let fans: Vec<(String, String)> = self
+            .fan_rpm
+            .iter()
+            .map(|(name, rpm)| (format!("{{fan=\"{}\"}}", name), rpm.to_string()))
+            .collect();
+        if !fans.is_empty() {
+            metric("lights_out_fan_rpm", "gauge", "AIO fan speeds", &fans);
+        }
+        metric(
+            "lights_out_temp_read_failures_total",
+            "counter",
+            "Failed CPU temperature reads",
+            &[(String::new(), self.temp_read_failures.to_string())],
+        );
+        metric(
+            "lights_out_daemon_uptime_seconds",
+            "gauge",
+            "Time since the daemon started",
+            &[(String::new(), self.started.elapsed().as_secs().to_string())],
+        );
+        out
+    }
+}
+
+/// Serve `GET /metrics` on `bind`:`port` from a background thread
+pub fn spawn_server(bind: IpAddr, port: u16, metrics: Arc<Mutex<MetricsSnapshot>>) -> Result<()> {
+    let addr = SocketAddr::new(bind, port);
+    let server = tiny_http::Server::http(addr)
+        .map_err(|e| anyhow!("Failed to listen on {}: {}", addr, e))?;
+
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let response = if request.url() == "/metrics" {
+                let body = metrics.lock().map(|m| m.render()).unwrap_or_default();
+                let content_type = tiny_http::Header::from_bytes(
+                    &b"Content-Type"[..],
+                    &b"text/plain; version=0.0.4"[..],
+                )
+                .expect("static header is valid");
+                tiny_http::Response::from_string(body).with_header(content_type)
+            } else {
+                tiny_http::Response::from_string("Not found\n").with_status_code(404)
+            };
+            if let Err(e) = request.respond(response) {
+                eprintln!("  Warning: Failed to answer metrics request: {}", e);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Pushes one line-protocol point per daemon cycle to the InfluxDB 2.x write API
+///
+/// Writes happen on a background thread, so a slow or dead server never delays
+/// the temperature sends.
+pub struct InfluxExporter {
+    host: String,
+    queue: SyncSender<String>,
+}
+
+impl InfluxExporter {
+    pub fn new(url: &str, token: &str, bucket: &str, org: Option<&str>) -> InfluxExporter {
+        let mut write_url = format!(
+            "{}/api/v2/write?bucket={}&precision=s",
+            url.trim_end_matches('/'),
+            query_escape(bucket)
+        );
+        if let Some(org) = org {
+            write_url += &format!("&org={}", query_escape(org));
+        }
+        let host = std::fs::read_to_string("/proc/sys/kernel/hostname")
+            .map(|name| name.trim().to_string())
+            .unwrap_or_else(|_| "localhost".to_string());
+        let (queue, points) = sync_channel(INFLUX_QUEUE_LEN);
+        let token = token.to_string();
+        std::thread::spawn(move || influx_writer(&write_url, &token, points));
+        InfluxExporter { host, queue }
+    }
+
+    /// `cpu_cooling,host=<host> cpu_temp=55i,pump_rpm=2400i,fan_mode="smart"`,
+    /// leaving out fields that weren't read; None if there is nothing to send
+    pub fn line(
+        &self,
+        cpu_temp: Option<i32>,
+        pump_rpm: Option<u16>,
+        fan_mode: Option<&str>,
+    ) -> Option<String> {
+        let mut fields = Vec::new();
+        if let Some(temp) = cpu_temp {
+            fields.push(format!("cpu_temp={}i", temp));
+        }
+        if let Some(rpm) = pump_rpm {
+            fields.push(format!("pump_rpm={}i", rpm));
+        }
+        if let Some(mode) = fan_mode {
+            let escaped = mode.replace('\\', "\\\\").replace('"', "\\\"");
+            fields.push(format!("fan_mode=\"{}\"", escaped));
+        }
+        if fields.is_empty() {
+            return None;
+        }
+        let host: String = self
+            .host
+            .chars()
+            .flat_map(|c| match c {
+                ',' | '=' | ' ' => vec!['\\', c],
+                _ => vec![c],
+            })
+            .collect();
+        Some(format!("cpu_cooling,host={} {}", host, fields.join(",")))
+    }
+
+    /// Queue a point for the writer thread (dropped if the queue is full)
+    pub fn push(&self, line: String) {
+        let _ = self.queue.try_send(line);
+    }
+}
+
+/// Writer thread: send queued points, warning about failures at most once
+/// per INFLUX_WARN_INTERVAL_SECS
+fn influx_writer(write_url: &str, token: &str, points: Receiver<String>) {
+    let mut last_warning: Option<Instant> = None;
+    let mut suppressed = 0u64;
+    for line in points {
+        let result = ureq::post(write_url)
+            .timeout(Duration::from_secs(INFLUX_TIMEOUT_SECS))
+            .set("Authorization", &format!("Token {}", token))
+            .set("Content-Type", "text/plain; charset=utf-8")
+            .send_string(&line)
+            .with_context(|| format!("Failed to write to {}", write_url));
+        match result {
+            Ok(_) => {
+                if last_warning.take().is_some() {
+                    eprintln!("  InfluxDB writes succeeded again");
+                }
+                suppressed = 0;
+            }
+            Err(e) => {
+                let due = last_warning.is_none_or(|at| {
+                    at.elapsed() >= Duration::from_secs(INFLUX_WARN_INTERVAL_SECS)
+                });
+                if !due {
+                    suppressed += 1;
+                    continue;
+                }
+                if suppressed > 0 {
+                    eprintln!(
+                        "  Warning: {:#} ({} more failures not shown)",
+                        e, suppressed
+                    );
+                } else {
+                    eprintln!("  Warning: {:#}", e);
+                }
+                last_warning = Some(Instant::now());
+                suppressed = 0;
+            }
+        }
+    }
+}
+
+/// Percent-encode a query parameter value
+fn query_escape(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
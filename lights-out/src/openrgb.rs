@@ -0,0 +1,117 @@
+// OpenRGB profile (.orp) import
+//
+// Only the parts needed to recreate per-zone mode/color/speed are read;
+// unknown fields are ignored so newer OpenRGB releases still load.
+
+use crate::color::Rgb;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Profile {
+    /// 1: modes are per controller, 2: zones may carry their own mode
+    #[serde(default = "default_version")]
+    pub version: u32,
+    pub controllers: Vec<Controller>,
+}
+
+fn default_version() -> u32 {
+    1
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Controller {
+    pub name: String,
+    /// Informational only; `zones` is authoritative
+    #[serde(default)]
+    pub num_zones: usize,
+    #[serde(default)]
+    pub active_mode: usize,
+    #[serde(default)]
+    pub modes: Vec<Mode>,
+    #[serde(default)]
+    pub zones: Vec<Zone>,
+    /// Per-LED colors, used when neither zone nor mode has any
+    #[serde(default)]
+    pub colors: Vec<Color>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Mode {
+    pub name: String,
+    #[serde(default)]
+    pub speed: Option<u32>,
+    #[serde(default)]
+    pub colors: Vec<Color>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Zone {
+    pub name: String,
+    /// Version 2 only
+    #[serde(default)]
+    pub mode: Option<Mode>,
+    #[serde(default)]
+    pub colors: Vec<Color>,
+}
+
+/// OpenRGB stores colors as 0x00BBGGRR; "#rrggbb" strings are accepted too
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Color {
+    Packed(u32),
+    Text(String),
+}
+
+impl Color {
+    pub fn from_rgb(rgb: Rgb) -> Color {
+        Color::Packed(rgb.r as u32 | ((rgb.g as u32) << 8) | ((rgb.b as u32) << 16))
+    }
+
+    pub fn to_rgb(&self) -> Result<Rgb> {
+        match self {
+            Color::Packed(v) => Ok(Rgb::new(*v as u8, (*v >> 8) as u8, (*v >> 16) as u8)),
+            Color::Text(s) => s.parse(),
+        }
+    }
+}
+
+impl Profile {
+    /// Format version written by `save`
+    pub const VERSION: u32 = 2;
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)? + "\n";
+        crate::state::write_atomic(path, content.as_bytes())
+    }
+
+    pub fn load(path: &Path) -> Result<Profile> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let profile: Profile = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        if !(1..=Profile::VERSION).contains(&profile.version) {
+            anyhow::bail!("Unsupported OpenRGB profile version {}", profile.version);
+        }
+        Ok(profile)
+    }
+}
+
+impl Controller {
+    /// Mode in effect for a zone: its own (version 2) or the controller's active mode
+    pub fn zone_mode(&self, zone: &Zone) -> Option<Mode> {
+        zone.mode
+            .clone()
+            .or_else(|| self.modes.get(self.active_mode).cloned())
+    }
+
+    /// Primary color of a zone, falling back to the mode's and then the controller's colors
+    pub fn zone_color<'a>(&'a self, zone: &'a Zone, mode: Option<&'a Mode>) -> Option<&'a Color> {
+        zone.colors
+            .first()
+            .or_else(|| mode.and_then(|m| m.colors.first()))
+            .or_else(|| self.colors.first())
+    }
+}
@@ -0,0 +1,186 @@
+// Last-applied device state for write-only devices (~/.local/share/lights-out)
+
+use crate::color::Rgb;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tempfile::NamedTempFile;
+
+/// Directory holding state files ($XDG_DATA_HOME/lights-out)
+pub fn data_dir() -> PathBuf {
+    let base = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .unwrap_or_else(|| PathBuf::from("/var/lib"));
+    base.join("lights-out")
+}
+
+/// Write a file by writing a sibling temp file and renaming it over the target
+///
+/// Readers see either the old or the new content, never a partial write.
+pub fn write_atomic(path: &Path, content: &[u8]) -> Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let mut tmp = NamedTempFile::new_in(dir)
+        .with_context(|| format!("Failed to create temp file in {}", dir.display()))?;
+    tmp.write_all(content)
+        .with_context(|| format!("Failed to write {}", tmp.path().display()))?;
+    // Temp files are created 0600; keep the file readable like a plain fs::write
+    tmp.as_file()
+        .set_permissions(fs::Permissions::from_mode(0o644))
+        .with_context(|| format!("Failed to set permissions on {}", tmp.path().display()))?;
+    tmp.persist(path)
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+/// Schema version written to lianli-state.json
+pub const LIANLI_STATE_VERSION: u32 = 1;
+
+/// Cached LianLi hub state, since the hub can't be read back
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LianliState {
+    /// Schema/protocol version; files from newer versions are refused
+    pub version: u32,
+    pub channels: BTreeMap<u8, LianliChannelState>,
+}
+
+impl Default for LianliState {
+    fn default() -> Self {
+        LianliState {
+            version: LIANLI_STATE_VERSION,
+            channels: BTreeMap::new(),
+        }
+    }
+}
+
+/// Raw commit packet bytes and colors last sent to one channel
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LianliChannelState {
+    pub mode: u8,
+    pub speed: u8,
+    pub direction: u8,
+    pub brightness: u8,
+    pub fan_color: Rgb,
+    pub edge_color: Rgb,
+}
+
+impl Default for LianliChannelState {
+    fn default() -> Self {
+        LianliChannelState {
+            mode: crate::lianli::MODE_STATIC,
+            speed: crate::lianli::SPEED_VERY_SLOW,
+            direction: crate::lianli::DIRECTION_LEFT_TO_RIGHT,
+            brightness: crate::lianli::BRIGHTNESS_FULL,
+            fan_color: Rgb::BLACK,
+            edge_color: Rgb::BLACK,
+        }
+    }
+}
+
+impl LianliState {
+    pub fn path() -> PathBuf {
+        data_dir().join("lianli-state.json")
+    }
+
+    /// Load the cached state, or an empty one if nothing was saved yet
+    pub fn load() -> Result<LianliState> {
+        LianliState::load_from(&LianliState::path())
+    }
+
+    /// Load a state file, or an empty state if it doesn't exist
+    pub fn load_from(path: &Path) -> Result<LianliState> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(LianliState::default()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        let state: LianliState = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        if state.version > LIANLI_STATE_VERSION {
+            anyhow::bail!(
+                "{} has state version {}, this build supports up to {}",
+                path.display(),
+                state.version,
+                LIANLI_STATE_VERSION
+            );
+        }
+        Ok(state)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&LianliState::path())
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        let state = LianliState {
+            version: LIANLI_STATE_VERSION,
+            channels: self.channels.clone(),
+        };
+        let content = serde_json::to_string_pretty(&state)? + "\n";
+        write_atomic(path, content.as_bytes())
+    }
+
+    /// State of one channel (defaults if it was never written)
+    pub fn channel(&self, channel: u8) -> LianliChannelState {
+        self.channels.get(&channel).copied().unwrap_or_default()
+    }
+}
+
+/// ENE GPU registers saved before the LEDs were disabled
+///
+/// Kept in /tmp since it only needs to survive until the LEDs are re-enabled.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GpuState {
+    /// Unix time the registers were read
+    pub timestamp: u64,
+    /// Register address -> value
+    pub registers: BTreeMap<u16, u8>,
+}
+
+impl GpuState {
+    pub fn path() -> PathBuf {
+        PathBuf::from("/tmp/lights-out-gpu-state.json")
+    }
+
+    /// An empty state stamped with the current time
+    pub fn now() -> GpuState {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        GpuState {
+            timestamp,
+            registers: BTreeMap::new(),
+        }
+    }
+
+    /// Load the saved state, or None if nothing was saved yet
+    pub fn load() -> Result<Option<GpuState>> {
+        let path = GpuState::path();
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        serde_json::from_str(&content)
+            .map(Some)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)? + "\n";
+        write_atomic(&GpuState::path(), content.as_bytes())
+    }
+}
@@ -0,0 +1,80 @@
+// systemd service notifications (sd_notify protocol over $NOTIFY_SOCKET)
+
+use anyhow::{Context, Result};
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::net::{SocketAddr, UnixDatagram};
+use std::time::Duration;
+
+/// Sends state changes to systemd; a no-op outside a notify-aware service
+pub struct SystemdNotifier {
+    socket: UnixDatagram,
+    addr: SocketAddr,
+}
+
+impl SystemdNotifier {
+    /// Connect to $NOTIFY_SOCKET, or None if not started by systemd with one
+    pub fn from_env() -> Result<Option<SystemdNotifier>> {
+        let Some(path) = std::env::var_os("NOTIFY_SOCKET") else {
+            return Ok(None);
+        };
+        let path = path.to_string_lossy();
+        // A leading '@' names a socket in the abstract namespace
+        let addr = match path.strip_prefix('@') {
+            Some(name) => SocketAddr::from_abstract_name(name.as_bytes()),
+            None => SocketAddr::from_pathname(path.as_ref()),
+        }
+        .with_context(|| format!("Invalid NOTIFY_SOCKET {}", path))?;
+        let socket = UnixDatagram::unbound().context("Failed to create notify socket")?;
+        Ok(Some(SystemdNotifier { socket, addr }))
+    }
+
+    /// Watchdog timeout requested by the service's WatchdogSec=, if any
+    pub fn watchdog_timeout() -> Option<Duration> {
+        std::env::var("WATCHDOG_USEC")
+            .ok()
+            .and_then(|usec| usec.parse().ok())
+            .map(Duration::from_micros)
+    }
+
+    fn notify(&self, state: &str) -> Result<()> {
+        self.socket
+            .send_to_addr(state.as_bytes(), &self.addr)
+            .with_context(|| format!("Failed to send {:?} to systemd", state))?;
+        Ok(())
+    }
+
+    /// Startup finished (for Type=notify services)
+    pub fn ready(&self) -> Result<()> {
+        self.notify("READY=1")
+    }
+
+    /// One-line status shown by `systemctl status`
+    pub fn status(&self, status: &str) -> Result<()> {
+        self.notify(&format!("STATUS={}", status))
+    }
+
+    pub fn stopping(&self) -> Result<()> {
+        self.notify("STOPPING=1")
+    }
+
+    /// Reset the WatchdogSec= timer
+    pub fn watchdog_ping(&self) -> Result<()> {
+        self.notify("WATCHDOG=1")
+    }
+}
+
+/// Quote one word of an ExecStart= line, so paths with spaces, quotes or
+/// specifier/variable characters reach the program unchanged
+pub fn quote_exec_arg(arg: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        match c {
+            '\\' | '"' => quoted.extend(['\\', c]),
+            '%' => quoted.push_str("%%"),
+            '$' => quoted.push_str("$$"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,148 @@
+// Current desktop wallpaper lookup for `daemon --effect wallpaper-reactive`
+
+use crate::color::{dominant_color, Rgb};
+use anyhow::{anyhow, Context, Result};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often the wallpaper setting is re-read
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Path of the current wallpaper image, asking the running desktop first
+pub fn current_wallpaper() -> Result<PathBuf> {
+    let desktop = std::env::var("XDG_CURRENT_DESKTOP")
+        .unwrap_or_default()
+        .to_uppercase();
+    let mut lookups: Vec<fn() -> Result<PathBuf>> = vec![gnome_wallpaper, kde_wallpaper];
+    if desktop.contains("KDE") {
+        lookups.reverse();
+    }
+    if desktop.contains("XFCE") {
+        lookups.insert(0, xfce_wallpaper);
+    } else {
+        lookups.push(xfce_wallpaper);
+    }
+
+    let mut errors = Vec::new();
+    for lookup in lookups {
+        match lookup() {
+            Ok(path) => return Ok(path),
+            Err(e) => errors.push(format!("{:#}", e)),
+        }
+    }
+    Err(anyhow!(
+        "Could not find the wallpaper: {}",
+        errors.join("; ")
+    ))
+}
+
+/// Run a command and return its trimmed stdout
+fn command_output(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        return Err(anyhow!("{} exited with {}", program, output.status));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Turn a file:// URI (possibly quoted, possibly percent-encoded) into a path
+fn uri_to_path(uri: &str) -> PathBuf {
+    let uri = uri.trim().trim_matches('\'');
+    let path = uri.strip_prefix("file://").unwrap_or(uri);
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                decoded.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+    PathBuf::from(String::from_utf8_lossy(&decoded).into_owned())
+}
+
+fn gnome_wallpaper() -> Result<PathBuf> {
+    let scheme = command_output(
+        "gsettings",
+        &["get", "org.gnome.desktop.interface", "color-scheme"],
+    )
+    .unwrap_or_default();
+    let key = if scheme.contains("prefer-dark") {
+        "picture-uri-dark"
+    } else {
+        "picture-uri"
+    };
+    let uri = command_output("gsettings", &["get", "org.gnome.desktop.background", key])?;
+    Ok(uri_to_path(&uri))
+}
+
+fn kde_wallpaper() -> Result<PathBuf> {
+    let home = std::env::var_os("HOME").ok_or_else(|| anyhow!("HOME is not set"))?;
+    let path = PathBuf::from(home).join(".config/plasma-org.kde.plasma.desktop-appletsrc");
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("Image="))
+        .map(uri_to_path)
+        .ok_or_else(|| anyhow!("No Image= entry in {}", path.display()))
+}
+
+fn xfce_wallpaper() -> Result<PathBuf> {
+    let properties = command_output("xfconf-query", &["-c", "xfce4-desktop", "-l"])?;
+    let property = properties
+        .lines()
+        .find(|p| p.ends_with("/last-image"))
+        .ok_or_else(|| anyhow!("No last-image property in xfce4-desktop"))?;
+    let path = command_output("xfconf-query", &["-c", "xfce4-desktop", "-p", property])?;
+    Ok(PathBuf::from(path))
+}
+
+/// Dominant wallpaper color, re-read when the wallpaper or its file changes
+#[derive(Default)]
+pub struct WallpaperWatcher {
+    checked_at: Option<Instant>,
+    source: Option<(PathBuf, Option<SystemTime>)>,
+    color: Option<Rgb>,
+}
+
+impl WallpaperWatcher {
+    /// Current color; the wallpaper setting is only checked every POLL_INTERVAL
+    pub fn poll(&mut self) -> Result<Option<Rgb>> {
+        if self
+            .checked_at
+            .is_some_and(|at| at.elapsed() < POLL_INTERVAL)
+        {
+            return Ok(self.color);
+        }
+        self.checked_at = Some(Instant::now());
+
+        let path = current_wallpaper()?;
+        let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+        let source = (path, modified);
+        if self.source.as_ref() != Some(&source) {
+            let img = image::open(&source.0)
+                .with_context(|| format!("Failed to load {}", source.0.display()))?;
+            let rgb = dominant_color(&img);
+            println!("  Wallpaper {}: dominant color {}", source.0.display(), rgb);
+            self.color = Some(rgb);
+            self.source = Some(source);
+        }
+        Ok(self.color)
+    }
+}